@@ -0,0 +1,28 @@
+// Render time of the random spheres scene with and without a BVH, run with
+// `cargo bench`. The image is small so that the linear traversal stays bearable
+
+use std::time::{Duration, Instant};
+
+use ozopathtracer_rs::{Camera, CameraSettings, Hitable, HitableBvh, LightList, Scene, random_spheres_scene};
+
+fn time_render(camera: &Camera, world: &dyn Hitable, lights: &LightList) -> Duration {
+	let start: Instant = Instant::now();
+	camera.render(world, lights);
+	start.elapsed()
+}
+
+fn main() {
+	let scene: Scene = random_spheres_scene();
+	let settings: CameraSettings = CameraSettings { image_width: 200, samples_per_pixel: 8, ..scene.camera.clone() };
+	let camera: Camera = settings.build();
+
+	let build_start: Instant = Instant::now();
+	let bvh: HitableBvh = HitableBvh::from_list(&scene.world);
+	let build_time: Duration = build_start.elapsed();
+
+	let list_time: Duration = time_render(&camera, &scene.world, &scene.lights);
+	let bvh_time: Duration = time_render(&camera, &bvh, &scene.lights);
+	println!("random spheres, {} objects, {}x{} at {} spp", scene.world.objects().len(), camera.image_width(), camera.image_height(), settings.samples_per_pixel);
+	println!("  list: {:>10.3?}", list_time);
+	println!("  BVH:  {:>10.3?} ({} nodes built in {:.3?}), {:.1}x faster", bvh_time, bvh.node_count(), build_time, list_time.as_secs_f64() / bvh_time.as_secs_f64());
+}
@@ -0,0 +1,89 @@
+use crate::{
+	vec::{Vec3, Point3},
+	ray::Ray
+};
+
+#[derive(Clone, Copy)]
+pub struct Aabb {
+	pub min: Point3,
+	pub max: Point3,
+}
+
+impl Aabb {
+	pub fn new(a: Point3, b: Point3) -> Aabb {
+		// Points may be given in any order, keep the smallest coordinates in min
+		Aabb {
+			min: Point3::new(a.x.min(b.x), a.y.min(b.y), a.z.min(b.z)),
+			max: Point3::new(a.x.max(b.x), a.y.max(b.y), a.z.max(b.z))
+		}
+	}
+
+	/// Box containing nothing. Any union with it returns the other box
+	pub const fn empty() -> Aabb {
+		Aabb {
+			min: Point3::new(f64::INFINITY, f64::INFINITY, f64::INFINITY),
+			max: Point3::new(f64::NEG_INFINITY, f64::NEG_INFINITY, f64::NEG_INFINITY)
+		}
+	}
+
+	pub fn surrounding(a: &Aabb, b: &Aabb) -> Aabb {
+		Aabb {
+			min: Point3::new(a.min.x.min(b.min.x), a.min.y.min(b.min.y), a.min.z.min(b.min.z)),
+			max: Point3::new(a.max.x.max(b.max.x), a.max.y.max(b.max.y), a.max.z.max(b.max.z))
+		}
+	}
+
+	pub fn include_point(&self, p: Point3) -> Aabb {
+		Aabb::surrounding(self, &Aabb { min: p, max: p })
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.min.x > self.max.x || self.min.y > self.max.y || self.min.z > self.max.z
+	}
+
+	pub fn extent(&self) -> Vec3 {
+		self.max - self.min
+	}
+
+	pub fn centroid(&self) -> Point3 {
+		0.5 * (self.min + self.max)
+	}
+
+	pub fn surface_area(&self) -> f64 {
+		if self.is_empty() {
+			return 0.0;
+		}
+		let d: Vec3 = self.extent();
+		2.0 * (d.x * d.y + d.y * d.z + d.z * d.x)
+	}
+
+	/// Index of the axis along which the box is the largest
+	pub fn longest_axis(&self) -> usize {
+		let d: Vec3 = self.extent();
+		if d.x > d.y && d.x > d.z { 0 } else if d.y > d.z { 1 } else { 2 }
+	}
+
+	pub fn hit(&self, ray: &Ray, ray_tmin: f64, ray_tmax: f64) -> bool {
+		let origin: Point3 = ray.origin();
+		let dir: Vec3 = ray.dir();
+		let mut t_min: f64 = ray_tmin;
+		let mut t_max: f64 = ray_tmax;
+
+		// Slab test, one axis at a time
+		for axis in 0..3 {
+			let inv_d: f64 = 1.0 / dir[axis];
+			let mut t0: f64 = (self.min[axis] - origin[axis]) * inv_d;
+			let mut t1: f64 = (self.max[axis] - origin[axis]) * inv_d;
+			if inv_d < 0.0 {
+				std::mem::swap(&mut t0, &mut t1);
+			}
+
+			t_min = if t0 > t_min { t0 } else { t_min };
+			t_max = if t1 < t_max { t1 } else { t_max };
+			if t_max < t_min {
+				return false;
+			}
+		}
+		true
+	}
+}
@@ -0,0 +1,40 @@
+use serde::Deserialize;
+
+use crate::{
+	color::{Color, BLACK, WHITE},
+	ray::Ray,
+	vec::Vec3
+};
+
+/// Radiance arriving along rays that escape the scene
+#[derive(Clone, Copy, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum Background {
+	/// Same color in every direction
+	Solid { color: Color },
+	/// Vertical blend from `bottom` straight down to `top` straight up
+	Gradient { bottom: Color, top: Color },
+	/// No light from outside of the scene, only emissive objects light it
+	None,
+}
+
+impl Default for Background {
+	fn default() -> Background {
+		// Sky of the book scenes
+		Background::Gradient { bottom: WHITE, top: Color::new(0.5, 0.7, 1.0) }
+	}
+}
+
+impl Background {
+	pub fn color(&self, ray: &Ray) -> Color {
+		match self {
+			Background::Solid { color } => *color,
+			Background::Gradient { bottom, top } => {
+				let unit_direction: Vec3 = ray.dir().unit_vector();
+				let a: f64 = 0.5 * (unit_direction.y + 1.0);
+				(1.0 - a) * *bottom + a * *top
+			},
+			Background::None => BLACK
+		}
+	}
+}
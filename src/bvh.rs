@@ -0,0 +1,299 @@
+use std::sync::Arc;
+
+use crate::{
+	aabb::Aabb,
+	hit::{HitRecord, Hitable, HitableList},
+	ray::Ray,
+	sampler::Sampler,
+	vec::Point3
+};
+
+const SAH_BUCKETS: usize = 12;			// Number of bins used to evaluate split candidates
+const MAX_PRIMITIVES_IN_LEAF: usize = 4;	// Leaves are only forced to split above this count
+const TRAVERSAL_COST: f64 = 1.0;		// Cost of visiting a node relative to a primitive test
+
+/// Node of a flattened BVH. Interior nodes store their first child right after them
+/// and the index of the second one. Leaves store a range of the primitive index list
+#[derive(Clone, Copy)]
+pub struct BvhNode {
+	bbox: Aabb,
+	offset: usize,	// Leaf: first primitive index. Interior: second child node
+	count: usize,	// Number of primitives in the leaf, zero for interior nodes
+	axis: usize,	// Split axis of interior nodes
+}
+
+impl BvhNode {
+	pub fn is_leaf(&self) -> bool {
+		self.count > 0
+	}
+}
+
+/// Bounding volume hierarchy over any indexed set of primitives. It only knows their
+/// bounding boxes, the intersection of each primitive is delegated to the caller
+pub struct Bvh {
+	nodes: Vec<BvhNode>,
+	indices: Vec<usize>,
+}
+
+struct BuildPrimitive {
+	index: usize,
+	bbox: Aabb,
+	centroid: Point3,
+}
+
+#[derive(Clone, Copy)]
+struct SahBucket {
+	count: usize,
+	bbox: Aabb,
+}
+
+impl Bvh {
+	/// Build the hierarchy with a binned surface area heuristic
+	pub fn new(bounds: &[Aabb]) -> Bvh {
+		let mut primitives: Vec<BuildPrimitive> = bounds.iter()
+			.enumerate()
+			.map(|(index, bbox)| BuildPrimitive { index, bbox: *bbox, centroid: bbox.centroid() })
+			.collect();
+
+		let mut bvh: Bvh = Bvh {
+			nodes: Vec::with_capacity(2 * bounds.len()),
+			indices: Vec::with_capacity(bounds.len())
+		};
+
+		if !primitives.is_empty() {
+			bvh.build_recursive(&mut primitives);
+		}
+		bvh
+	}
+
+	pub fn node_count(&self) -> usize {
+		self.nodes.len()
+	}
+
+	pub fn bounding_box(&self) -> Aabb {
+		match self.nodes.first() {
+			Some(root) => root.bbox,
+			None => Aabb::empty()
+		}
+	}
+
+	/// Walks the hierarchy front to back and returns the closest hit reported by
+	/// `hit_primitive`, which is called with a primitive index and the current t range
+	pub fn hit<F>(&self, ray: &Ray, ray_tmin: f64, ray_tmax: f64, mut hit_primitive: F) -> Option<HitRecord>
+	where F: FnMut(usize, &Ray, f64, f64) -> Option<HitRecord> {
+		if self.nodes.is_empty() {
+			return None;
+		}
+
+		let dir_is_negative: [bool; 3] = [ray.dir().x < 0.0, ray.dir().y < 0.0, ray.dir().z < 0.0];
+		let mut closest_so_far: f64 = ray_tmax;
+		let mut hit_anything: Option<HitRecord> = None;
+
+		let mut stack: Vec<usize> = Vec::with_capacity(64);
+		stack.push(0);
+
+		while let Some(node_index) = stack.pop() {
+			let node: &BvhNode = &self.nodes[node_index];
+			if !node.bbox.hit(ray, ray_tmin, closest_so_far) {
+				continue;
+			}
+
+			if node.is_leaf() {
+				for &index in &self.indices[node.offset..node.offset + node.count] {
+					if let Some(hit) = hit_primitive(index, ray, ray_tmin, closest_so_far) {
+						closest_so_far = hit.t;
+						hit_anything = Some(hit);
+					}
+				}
+			} else if dir_is_negative[node.axis] {
+				// Visit the child on the ray side first, it is pushed last
+				stack.push(node_index + 1);
+				stack.push(node.offset);
+			} else {
+				stack.push(node.offset);
+				stack.push(node_index + 1);
+			}
+		}
+
+		hit_anything
+	}
+
+	fn build_recursive(&mut self, primitives: &mut [BuildPrimitive]) -> usize {
+		let node_index: usize = self.nodes.len();
+		let bbox: Aabb = primitives.iter()
+			.fold(Aabb::empty(), |bbox, primitive| Aabb::surrounding(&bbox, &primitive.bbox));
+
+		self.nodes.push(BvhNode { bbox, offset: 0, count: 0, axis: 0 });
+
+		if primitives.len() == 1 {
+			self.make_leaf(node_index, primitives);
+			return node_index;
+		}
+
+		let centroid_bounds: Aabb = primitives.iter()
+			.fold(Aabb::empty(), |bounds, primitive| bounds.include_point(primitive.centroid));
+		let axis: usize = centroid_bounds.longest_axis();
+		let axis_min: f64 = centroid_bounds.min[axis];
+		let axis_extent: f64 = centroid_bounds.max[axis] - axis_min;
+
+		// All centroids in the same place, there is no way to split them
+		if axis_extent <= 0.0 {
+			self.make_leaf(node_index, primitives);
+			return node_index;
+		}
+
+		let bucket_of = |primitive: &BuildPrimitive| -> usize {
+			let b: usize = (SAH_BUCKETS as f64 * (primitive.centroid[axis] - axis_min) / axis_extent) as usize;
+			b.min(SAH_BUCKETS - 1)
+		};
+
+		let mut buckets: [SahBucket; SAH_BUCKETS] = [SahBucket { count: 0, bbox: Aabb::empty() }; SAH_BUCKETS];
+		for primitive in primitives.iter() {
+			let bucket: &mut SahBucket = &mut buckets[bucket_of(primitive)];
+			bucket.count += 1;
+			bucket.bbox = Aabb::surrounding(&bucket.bbox, &primitive.bbox);
+		}
+
+		// Cost of splitting after each bucket
+		let mut best_split: usize = 0;
+		let mut best_cost: f64 = f64::INFINITY;
+		for split in 0..SAH_BUCKETS - 1 {
+			let (left, right) = buckets.split_at(split + 1);
+			let (left_count, left_bbox) = Bvh::merge_buckets(left);
+			let (right_count, right_bbox) = Bvh::merge_buckets(right);
+
+			let cost: f64 = TRAVERSAL_COST + 
+				(left_count as f64 * left_bbox.surface_area() + right_count as f64 * right_bbox.surface_area()) / bbox.surface_area();
+			if cost < best_cost {
+				best_cost = cost;
+				best_split = split;
+			}
+		}
+
+		let leaf_cost: f64 = primitives.len() as f64;
+		if primitives.len() <= MAX_PRIMITIVES_IN_LEAF && leaf_cost <= best_cost {
+			self.make_leaf(node_index, primitives);
+			return node_index;
+		}
+
+		let mut mid: usize = Bvh::partition(primitives, |primitive| bucket_of(primitive) <= best_split);
+		if mid == 0 || mid == primitives.len() {
+			// Degenerated bucket distribution, fall back to a median split
+			mid = primitives.len() / 2;
+			primitives.sort_unstable_by(|a, b| a.centroid[axis].total_cmp(&b.centroid[axis]));
+		}
+
+		let (left, right) = primitives.split_at_mut(mid);
+		self.build_recursive(left);
+		let second_child: usize = self.build_recursive(right);
+
+		let node: &mut BvhNode = &mut self.nodes[node_index];
+		node.offset = second_child;
+		node.axis = axis;
+		node_index
+	}
+
+	fn make_leaf(&mut self, node_index: usize, primitives: &[BuildPrimitive]) {
+		let node: &mut BvhNode = &mut self.nodes[node_index];
+		node.offset = self.indices.len();
+		node.count = primitives.len();
+		self.indices.extend(primitives.iter().map(|primitive| primitive.index));
+	}
+
+	fn merge_buckets(buckets: &[SahBucket]) -> (usize, Aabb) {
+		buckets.iter().fold((0, Aabb::empty()), |(count, bbox), bucket| {
+			(count + bucket.count, Aabb::surrounding(&bbox, &bucket.bbox))
+		})
+	}
+
+	// In-place partition, returns the number of primitives satisfying the predicate
+	fn partition<P>(primitives: &mut [BuildPrimitive], predicate: P) -> usize
+	where P: Fn(&BuildPrimitive) -> bool {
+		let mut first: usize = 0;
+		for i in 0..primitives.len() {
+			if predicate(&primitives[i]) {
+				primitives.swap(first, i);
+				first += 1;
+			}
+		}
+		first
+	}
+}
+
+/// Set of objects traced through a BVH instead of the linear walk of HitableList
+pub struct HitableBvh {
+	objects: Vec<Arc<dyn Hitable>>,
+	bvh: Bvh,
+}
+
+impl HitableBvh {
+	pub fn new(objects: Vec<Arc<dyn Hitable>>) -> HitableBvh {
+		let bounds: Vec<Aabb> = objects.iter().map(|object| object.bounding_box()).collect();
+		HitableBvh {
+			bvh: Bvh::new(&bounds),
+			objects
+		}
+	}
+
+	pub fn from_list(list: &HitableList) -> HitableBvh {
+		HitableBvh::new(list.objects().to_vec())
+	}
+
+	pub fn node_count(&self) -> usize {
+		self.bvh.node_count()
+	}
+}
+
+impl Hitable for HitableBvh {
+	fn hit(&self, ray: &Ray, ray_tmin: f64, ray_tmax: f64, sampler: &mut dyn Sampler) -> Option<HitRecord> {
+		self.bvh.hit(ray, ray_tmin, ray_tmax, |index, ray, t_min, t_max| {
+			self.objects[index].hit(ray, t_min, t_max, sampler)
+		})
+	}
+
+	fn bounding_box(&self) -> Aabb {
+		self.bvh.bounding_box()
+	}
+}
+
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{
+		random::Rng,
+		sampler::SamplerKind,
+		scene::random_spheres_scene,
+		vec::Vec3
+	};
+
+	#[test]
+	fn bvh_hits_match_linear_traversal() {
+		let list: HitableList = random_spheres_scene().world;
+		let mut rng: Rng = Rng::new(7);
+		let bvh: HitableBvh = HitableBvh::from_list(&list);
+		let mut sampler: Box<dyn Sampler> = SamplerKind::Independent.build(7, 1);
+
+		let mut hit_count: usize = 0;
+		for _ in 0..20000 {
+			let origin: Point3 = Point3::new(rng.f64_range(-15.0, 15.0), rng.f64_range(0.05, 6.0), rng.f64_range(-15.0, 15.0));
+			let direction: Vec3 = Vec3::random_range(&mut rng, -1.0, 1.0);
+			let ray: Ray = Ray::new(origin, direction);
+
+			match (bvh.hit(&ray, 0.001, f64::INFINITY, sampler.as_mut()), list.hit(&ray, 0.001, f64::INFINITY, sampler.as_mut())) {
+				(Some(bvh_hit), Some(list_hit)) => {
+					assert_eq!(bvh_hit.t, list_hit.t);
+					assert_eq!([bvh_hit.p.x, bvh_hit.p.y, bvh_hit.p.z], [list_hit.p.x, list_hit.p.y, list_hit.p.z]);
+					assert_eq!(
+						[bvh_hit.normal.x, bvh_hit.normal.y, bvh_hit.normal.z],
+						[list_hit.normal.x, list_hit.normal.y, list_hit.normal.z]
+					);
+					hit_count += 1;
+				},
+				(None, None) => (),
+				_ => panic!("BVH and list disagree on whether the ray from {} along {} hits", origin, direction)
+			}
+		}
+		assert!(hit_count > 1000, "too few rays hit anything to compare");
+	}
+}
@@ -1,36 +1,149 @@
 
 use crate::{
-	random::random_f64,
-	vec::{Vec3, Point3, cross},
-	color::{Color, BLACK, WHITE},
-	hit::{Hitable, HitableList},
-	ray::Ray
+	aabb::Aabb,
+	vec::{Vec3, Point3, cross, dot},
+	color::{Color, BLACK},
+	background::Background,
+	hit::{HitRecord, Hitable},
+	framebuffer::Framebuffer,
+	light::{LightList, LightSample, power_heuristic, same_distance},
+	sampler::{Sampler, SamplerKind},
+	ray::{Ray, RayDifferentials}
 };
 
-use image::{ImageBuffer, RgbImage};
+use rayon::prelude::*;
+use serde::{Deserialize, Deserializer, de::Error};
 
-use std::f64::INFINITY;
+/// Every parameter needed to build a camera. Missing entries of a scene file
+/// take the values of the default settings
+#[derive(Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CameraSettings {
+	pub image_width: u32,			// Rendered image width in pixel count
+	pub aspect_ratio: f64,			// Ratio of image width over height
+	pub vfov: f64,					// Vertical FOV
+	pub samples_per_pixel: u32,		// Count of random samples for each pixel
+	pub max_depth: u32,				// Maximum number of ray bounces into scene
+	pub look_from: Point3,			// Point camera is looking from
+	pub look_at: Point3,			// Point camera is looking at
+	pub vup: Vec3,					// Camera-relative "up" direction
+	#[serde(deserialize_with = "defocus_angle")]
+	pub defocus_angle: f64,			// Variation angle of rays through each pixel
+	pub focus_dist: f64,			// Distance from camera look_from point to plane of perfect focus
+	pub seed: u64,					// Seed for the render
+	pub sampler: SamplerKind,		// Distribution of the samples of each pixel
+}
+
+impl Default for CameraSettings {
+	fn default() -> CameraSettings {
+		CameraSettings {
+			image_width: 1200,
+			aspect_ratio: 16.0 / 9.0,
+			vfov: 20.0,
+			samples_per_pixel: 100,
+			max_depth: 50,
+			look_from: Point3::new(13.0, 2.0, 3.0),
+			look_at: Point3::new(0.0, 0.0, -1.0),
+			vup: Vec3::new(0.0, 1.0, 0.0),
+			defocus_angle: 0.6,
+			focus_dist: 10.0,
+			seed: 0,
+			sampler: SamplerKind::default()
+		}
+	}
+}
+
+// Scene files are checked while parsing, so the error points at the value
+fn defocus_angle<'de, D>(deserializer: D) -> Result<f64, D::Error>
+where D: Deserializer<'de> {
+	let value: f64 = f64::deserialize(deserializer)?;
+	if value < 0.0 || !value.is_finite() {
+		return Err(D::Error::custom(format!("defocus angle {} must be zero or greater", value)));
+	}
+	Ok(value)
+}
+
+impl CameraSettings {
+	/// Check the settings describe a camera that can be built
+	pub fn validate(&self) -> Result<(), String> {
+		if self.image_width == 0 {
+			return Err("image width must be at least one pixel".to_string());
+		}
+		if self.aspect_ratio <= 0.0 || !self.aspect_ratio.is_finite() {
+			return Err(format!("invalid aspect ratio {}", self.aspect_ratio));
+		}
+		if self.samples_per_pixel == 0 {
+			return Err("samples per pixel must be at least one".to_string());
+		}
+		if self.vfov <= 0.0 || self.vfov >= 180.0 {
+			return Err(format!("vertical FOV {} is outside of the (0, 180) degrees range", self.vfov));
+		}
+		if self.defocus_angle < 0.0 || !self.defocus_angle.is_finite() {
+			return Err(format!("defocus angle {} must be zero or greater", self.defocus_angle));
+		}
+		if self.focus_dist <= 0.0 {
+			return Err(format!("focus distance {} must be greater than zero", self.focus_dist));
+		}
+		if (self.look_from - self.look_at).length_squared() == 0.0 {
+			return Err("look_from and look_at must be different points".to_string());
+		}
+		if cross(self.vup, self.look_from - self.look_at).length_squared() == 0.0 {
+			return Err("vup must not be parallel to the view direction".to_string());
+		}
+		Ok(())
+	}
+
+	/// Move the camera along its view direction until the whole box fits the image,
+	/// looking at the box center and focusing on it
+	pub fn frame(&mut self, bounds: &Aabb) {
+		let radius: f64 = 0.5 * bounds.extent().length();
+		let vfov: f64 = self.vfov.to_radians();
+		let hfov: f64 = 2.0 * ((vfov / 2.0).tan() * self.aspect_ratio).atan();
+		let distance: f64 = radius / (vfov.min(hfov) / 2.0).sin();
+
+		self.look_at = bounds.centroid();
+		self.look_from = self.look_at + distance * (self.look_from - self.look_at).unit_vector();
+		self.focus_dist = distance;
+	}
+
+	pub fn build(&self) -> Camera {
+		let mut camera: Camera = Camera::new(
+			self.image_width, 
+			self.aspect_ratio, 
+			self.vfov, 
+			self.samples_per_pixel, 
+			self.max_depth, 
+			self.look_from, 
+			self.look_at, 
+			self.vup, 
+			self.defocus_angle, 
+			self.focus_dist
+		);
+		camera.set_seed(self.seed);
+		camera.set_sampler(self.sampler);
+		camera
+	}
+}
 
 pub struct Camera {
 	image_width: u32,		// Rendered image width in pixel count
 	image_height: u32,  	// Rendered image height in pixel count
-	aspect_ratio: f64,		// Ratio of image width over height
-	vfov: f64,				// Vertical view angel (FOV)
 	center: Vec3,			// Camera center
 	pixel00_loc: Vec3,		// Location of pixel 0, 0
 	pixel_delta_u: Vec3,	// Offset to pixel to the right
 	pixel_delta_v: Vec3,	// Offset to pixel below
 	samples_per_pixel: u32, // Count of random samples for each pixel
 	max_depth: u32,			// Maximum number of ray bounces into scene
-	u: Vec3,				// Camera frame basis vectors
-	v: Vec3,
-	w: Vec3,
 	defocus_disk_u: Vec3,	// Defocus disk horizontal radius
 	defocus_disk_v: Vec3,	// Defocus disk horizontal radius
 	defocus_angle: f64,		// Variation angle of rays through each pixel
+	seed: u64,				// Base seed of the per-sample random sequences
+	sampler: SamplerKind,	// Distribution of the samples of each pixel
+	background: Background,	// Radiance of rays escaping the scene
 }
 
 impl Camera {
+	#[allow(clippy::too_many_arguments)]
 	pub fn new(image_width: u32, aspect_ratio: f64, vfov: f64, samples_per_pixel: u32, max_depth: u32, look_from: Point3, look_at: Point3, vup: Vec3, defocus_angle: f64, focus_dist: f64) -> Camera {
 		let mut image_height: u32 = (image_width as f64 / aspect_ratio) as u32;
 		image_height = if image_height < 1 { 1 } else { image_height };
@@ -49,7 +162,7 @@ impl Camera {
 		let v: Vec3 = cross(w, u);
 
 		// Calculate the vector accross the horizontal and down the vertical viewport edges
-		let viewport_u: Vec3 = viewport_width as f64 * u;	// Vector across viewport horizontal edge
+		let viewport_u: Vec3 = viewport_width * u;	// Vector across viewport horizontal edge
 		let viewport_v: Vec3 = viewport_height * -v;		// Vector down viewport vertical edge
 
 		// Calculate the horizontal and vertical delta vecgor from pixel to pixel
@@ -66,113 +179,401 @@ impl Camera {
 		let defocus_disk_v: Vec3 = v * defocus_radius;
 
 		Camera {
-			image_width: image_width,
-			image_height: image_height,
-			aspect_ratio: aspect_ratio,
-			vfov: vfov,
+			image_width,
+			image_height,
 			center: camera_center,
 			pixel00_loc: viewport_upper_left + 0.5 * (pixel_delta_u + pixel_delta_v),
-			pixel_delta_u: pixel_delta_u,
-			pixel_delta_v: pixel_delta_v,
-			samples_per_pixel: samples_per_pixel,
-			max_depth: max_depth,
-			u: u,
-			v: v,
-			w: w,
-			defocus_angle: defocus_angle,
-			defocus_disk_u: defocus_disk_u,
-			defocus_disk_v: defocus_disk_v
-		}
-	}
-
-	pub fn render(&self, world: &HitableList, image_filename: &str) {
-		  
-	    // Image creation
- 	   let mut img: RgbImage = ImageBuffer::new(self.image_width, self.image_height);
-
-		print!("Rendering");
-		for y in 0..self.image_height {
-		//for y in self.image_height / 2..=self.image_height / 2 {
-			print!(".");
+			pixel_delta_u,
+			pixel_delta_v,
+			samples_per_pixel,
+			max_depth,
+			defocus_angle,
+			defocus_disk_u,
+			defocus_disk_v,
+			seed: 0,
+			sampler: SamplerKind::default(),
+			background: Background::default()
+		}
+	}
+
+	pub fn image_width(&self) -> u32 {
+		self.image_width
+	}
+
+	pub fn image_height(&self) -> u32 {
+		self.image_height
+	}
+
+	pub fn set_seed(&mut self, seed: u64) {
+		self.seed = seed;
+	}
+
+	pub fn set_sampler(&mut self, sampler: SamplerKind) {
+		self.sampler = sampler;
+	}
+
+	pub fn set_background(&mut self, background: Background) {
+		self.background = background;
+	}
+
+	/// Renders the world into a linear framebuffer, holding `samples_per_pixel`
+	/// samples for every pixel. Emissive objects also listed in `lights` are 
+	/// sampled directly from every diffuse surface.
+	///
+	/// ```
+	/// use std::sync::Arc;
+	/// use ozopathtracer_rs::{CameraSettings, HitableList, Lambertian, LightList, Point3, Sphere};
+	///
+	/// let world = HitableList::new(vec![
+	///     Arc::new(Sphere::new(Point3::new(0.0, 0.0, -1.0), 0.5,
+	///         Arc::new(Lambertian::new(Point3::new(0.5, 0.5, 0.5)))))
+	/// ]);
+	///
+	/// let camera = CameraSettings {
+	///     image_width: 16,
+	///     samples_per_pixel: 2,
+	///     look_from: Point3::new(0.0, 0.0, 1.0),
+	///     ..CameraSettings::default()
+	/// }.build();
+	///
+	/// let framebuffer = camera.render(&world, &LightList::default());
+	/// assert_eq!((framebuffer.width(), framebuffer.height()), (16, 9));
+	/// assert_eq!(framebuffer.sample_count(0, 0), 2);
+	/// ```
+	pub fn render(&self, world: &dyn Hitable, lights: &LightList) -> Framebuffer {
+		self.render_with_progress(world, lights, |_| {})
+	}
+
+	/// Same as [`Camera::render`], calling `progress` with the index of every row 
+	/// once it is completed. Rows are not completed in order.
+	pub fn render_with_progress<F>(&self, world: &dyn Hitable, lights: &LightList, progress: F) -> Framebuffer
+	where F: Fn(u32) + Sync {
+		let mut framebuffer: Framebuffer = Framebuffer::new(self.image_width, self.image_height);
+
+		// Every row is rendered by whichever worker thread picks it up. Each sample
+		// has its own sequence of dimensions, so the image does not depend on the scheduling
+		framebuffer.par_rows_mut().for_each(|(y, (sums, sample_counts))| {
+			let mut sampler: Box<dyn Sampler> = self.sampler.build(self.seed, self.samples_per_pixel);
 			for x in 0..self.image_width {
 				let mut pixel_color: Vec3 = Vec3::zeros();
-				for _sample in 0..self.samples_per_pixel {
-					let ray: Ray = self.get_ray(x, y);
-					pixel_color += self.ray_color(&ray, world, self.max_depth);
+				for sample in 0..self.samples_per_pixel {
+					sampler.start_sample(x, y as u32, sample);
+					let ray: Ray = self.get_ray(x, y as u32, sampler.as_mut());
+					pixel_color += self.ray_color(&ray, world, lights, self.max_depth, None, sampler.as_mut());
 				}
-				
-				// Write the final color
-				pixel_color = (pixel_color * (1.0 / self.samples_per_pixel as f64))
-					.sqrt()
-					.clamp(0.0, 0.999); 
-
-//				println!("[{}, {}] -> {}", x, y, pixel_color);
-
-				img.put_pixel(
-					x, 
-					y, 
-					image::Rgb([(pixel_color.x * 255.99) as u8, (pixel_color.y * 255.99) as u8, (pixel_color.z * 255.99) as u8]));
+
+				sums[x as usize] += pixel_color;
+				sample_counts[x as usize] += self.samples_per_pixel;
 			}
-		}
-		img.save(image_filename).unwrap();
-		println!(" Completed")
+			progress(y as u32);
+		});
+		framebuffer
 	}
 
-	fn get_ray(&self, x: u32, y: u32) -> Ray
+	fn get_ray(&self, x: u32, y: u32, sampler: &mut dyn Sampler) -> Ray
 	{
 		// Get a randomly-sampled camera ray for the pixel at location i,j, originating from
         // the camera defocus disk.
 		let pixel_center : Vec3 = 
 			self.pixel00_loc + (x as f64 * self.pixel_delta_u) + (y as f64 * self.pixel_delta_v);
-		let pixel_sample: Vec3 = pixel_center + self.pixel_sample_square();
+		let pixel_sample: Vec3 = pixel_center + self.pixel_sample_square(sampler);
 		
 		let ray_origin: Vec3 = if self.defocus_angle <= 0.0 {
 				self.center
 			} 
 			else {
-				self.defocus_disk_sample()
+				self.defocus_disk_sample(sampler)
 			};
 		let ray_direction:Vec3 = pixel_sample - ray_origin;
 
-		Ray::new(ray_origin, ray_direction)
+		// Rays through the next pixels share the lens sample. With many samples per
+		// pixel each one covers a fraction of it, so the offsets are scaled down
+		let spacing: f64 = (1.0 / (self.samples_per_pixel as f64).sqrt()).max(0.125);
+		let differentials: RayDifferentials = RayDifferentials {
+			x_origin: ray_origin,
+			x_dir: ray_direction + spacing * self.pixel_delta_u,
+			y_origin: ray_origin,
+			y_dir: ray_direction + spacing * self.pixel_delta_v
+		};
+
+		Ray::new(ray_origin, ray_direction).with_differentials(differentials)
 	}
 
-	fn pixel_sample_square(&self) -> Vec3 {
-		let px: f64 = -0.5 + random_f64();
-		let py: f64 = -0.5 + random_f64();
+	fn pixel_sample_square(&self, sampler: &mut dyn Sampler) -> Vec3 {
+		let (px, py) = sampler.get_2d();
+		let (px, py) = (px - 0.5, py - 0.5);
 		(px * self.pixel_delta_u) + (py * self.pixel_delta_v)
 	}
 
-	fn ray_color(&self, ray: &Ray, world: &HitableList, depth: u32) -> Color {
+	// `bsdf_pdf` is the density with which a non-specular bounce sampled `ray`. It is
+	// None for camera rays and specular bounces, whose emitters are never sampled directly
+	fn ray_color(&self, ray: &Ray, world: &dyn Hitable, lights: &LightList, depth: u32, bsdf_pdf: Option<f64>, sampler: &mut dyn Sampler) -> Color {
 
 		// If we've exceeded the ray bounce limit, no more light is gathering
 		if depth > 0 {
-			match world.hit(ray, 0.001, INFINITY) {
-				Some(hit) => {
-					match hit.material.scatter(ray, &hit) {
+			match world.hit(ray, 0.001, f64::INFINITY, sampler) {
+				Some(mut hit) => {
+					hit.set_footprint(ray);
+					let mut emitted: Color = hit.material.emitted(ray, &hit);
+
+					// Emitters found by a non-specular bounce were also reached by light sampling,
+					// each strategy only keeps its multiple importance sampling share. The light
+					// pdf is that of the light hit, other lights along the ray could not have
+					// lit this point. Glowing media are never sampled as lights
+					if let (Some(bsdf_pdf), false) = (bsdf_pdf, hit.material.is_volumetric()) {
+						let ray_length: f64 = ray.dir().length();
+						let light_pdf: f64 = lights.pdf(ray.origin(), ray.dir() / ray_length, hit.t * ray_length);
+						emitted = power_heuristic(bsdf_pdf, light_pdf) * emitted;
+					}
+
+					let radiance: Color = match hit.material.scatter(ray, &hit, sampler) {
 						Some(ray_interaction) => {
-							ray_interaction.attenuation() * self.ray_color(&ray_interaction.scattered(), world, depth - 1)
+							let scattered: Ray = ray_interaction.scattered();
+
+							if ray_interaction.is_specular() {
+								emitted + ray_interaction.attenuation() * self.ray_color(&scattered, world, lights, depth - 1, None, sampler)
+							} else {
+								emitted 
+									+ self.sample_lights(ray, &hit, world, lights, sampler)
+									+ ray_interaction.attenuation() * self.ray_color(&scattered, world, lights, depth - 1, Some(ray_interaction.pdf()), sampler)
+							}
 						},
 						None => {
-							BLACK
+							emitted
 						}
+					};
+
+					// A ray hitting a surface from the inside crossed the object, whose
+					// medium absorbs part of the light coming back along it
+					if hit.front_face {
+						radiance
+					} else {
+						hit.material.transmittance(&hit, hit.t * ray.dir().length()) * radiance
 					}
 				},
 				None => {
-					let unit_direction: Vec3 = ray.dir().unit_vector();
-					let a: f64 = 0.5 * (unit_direction.y + 1.0);
-					(1.0 - a) * WHITE + a * Color::new(0.5, 0.7, 1.0)
+					self.background.color(ray)
 				}
 			}
 		} else {
 			// If we've exceeded the ray bounce limit, no more light is gathered
-			WHITE
+			BLACK
 		}
 	}	
 
-	fn defocus_disk_sample(&self) -> Point3 {
+	// Direct light at a non-specular hit, from a point sampled on one of the lights
+	fn sample_lights(&self, ray: &Ray, hit: &HitRecord, world: &dyn Hitable, lights: &LightList, sampler: &mut dyn Sampler) -> Color {
+		let sample: LightSample = match lights.sample(hit.p, sampler) {
+			Some(sample) => sample,
+			None => return BLACK
+		};
+
+		let wi: Vec3 = sample.direction;
+		let wo: Vec3 = -ray.dir().unit_vector();
+		let bsdf: Color = hit.material.eval(hit, wi, wo);
+		if bsdf.near_zero() {
+			return BLACK;
+		}
+
+		// The light is only visible if the first thing hit is the sampled point
+		let shadow_ray: Ray = Ray::new(hit.p, wi);
+		match world.hit(&shadow_ray, 0.001, f64::INFINITY, sampler) {
+			Some(light_hit) if same_distance(light_hit.t, sample.distance) => {
+				let emitted: Color = light_hit.material.emitted(&shadow_ray, &light_hit);
+				let weight: f64 = power_heuristic(sample.pdf, hit.material.pdf(hit, wi, wo));
+				let cosine: f64 = if hit.material.is_volumetric() { 1.0 } else { dot(wi, hit.normal).abs() };
+				(weight * cosine / sample.pdf) * bsdf * emitted
+			},
+			_ => BLACK
+		}
+	}
+
+	fn defocus_disk_sample(&self, sampler: &mut dyn Sampler) -> Point3 {
 		// Returns a random point in the camera defocus disk.
-        let p: Point3 = Vec3::random_in_unit_disk();
+        let p: Point3 = Vec3::random_in_unit_disk(sampler);
         self.center + (p.x * self.defocus_disk_u) + (p.y * self.defocus_disk_v)
 	}
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+	use std::path::Path;
+
+	use super::*;
+	use crate::{
+		bvh::HitableBvh,
+		scene::{Scene, parse_scene}
+	};
+
+	// Diffuse, metal and glass spheres lit by the sky and by a sphere light, so every
+	// kind of bounce and the light sampling draw random numbers
+	const SCENE: &str = r#"
+		[camera]
+		image_width = 24
+		aspect_ratio = 1.5
+		samples_per_pixel = 4
+		max_depth = 8
+		look_from = [0.0, 1.0, 6.0]
+		look_at = [0.0, 0.5, 0.0]
+		defocus_angle = 1.0
+		focus_dist = 6.0
+
+		[materials.ground]
+		type = "lambertian"
+		albedo = [0.5, 0.5, 0.5]
+
+		[materials.metal]
+		type = "metal"
+		albedo = [0.8, 0.6, 0.2]
+		fuzz = 0.3
+
+		[materials.glass]
+		type = "dielectric"
+		index_of_refraction = 1.5
+
+		[materials.light]
+		type = "diffuse_light"
+		emit = [4.0, 4.0, 4.0]
+
+		[[objects]]
+		type = "sphere"
+		center = [0.0, -1000.0, 0.0]
+		radius = 1000.0
+		material = "ground"
+
+		[[objects]]
+		type = "sphere"
+		center = [-1.1, 0.5, 0.0]
+		radius = 0.5
+		material = "metal"
+
+		[[objects]]
+		type = "sphere"
+		center = [0.0, 0.5, 0.0]
+		radius = 0.5
+		material = "glass"
+
+		[[objects]]
+		type = "sphere"
+		center = [1.1, 1.5, 0.5]
+		radius = 0.4
+		material = "light"
+	"#;
+
+	// Diffuse spheres under a quad light with few bounces, so the error left after a
+	// few samples comes mostly from the pixel, lens and light dimensions
+	const LIT_SCENE: &str = r#"
+		[camera]
+		image_width = 16
+		aspect_ratio = 1.0
+		vfov = 40.0
+		samples_per_pixel = 16
+		max_depth = 3
+		look_from = [0.0, 1.0, 5.0]
+		look_at = [0.0, 0.5, 0.0]
+		defocus_angle = 2.0
+		focus_dist = 5.0
+
+		[materials.white]
+		type = "lambertian"
+		albedo = [0.7, 0.7, 0.7]
+
+		[materials.red]
+		type = "lambertian"
+		albedo = [0.7, 0.2, 0.2]
+
+		[materials.light]
+		type = "diffuse_light"
+		emit = [6.0, 6.0, 6.0]
+
+		[[objects]]
+		type = "sphere"
+		center = [0.0, -1000.0, 0.0]
+		radius = 1000.0
+		material = "white"
+
+		[[objects]]
+		type = "sphere"
+		center = [0.0, 0.7, 0.0]
+		radius = 0.7
+		material = "red"
+
+		[[objects]]
+		type = "quad"
+		q = [-0.5, 2.5, -0.5]
+		u = [1.0, 0.0, 0.0]
+		v = [0.0, 0.0, 1.0]
+		material = "light"
+	"#;
+
+	fn render_in_pool(scene: &Scene, camera: &Camera, threads: usize) -> Framebuffer {
+		let world: HitableBvh = HitableBvh::from_list(&scene.world);
+		rayon::ThreadPoolBuilder::new()
+			.num_threads(threads)
+			.build()
+			.expect("thread pool")
+			.install(|| camera.render(&world, &scene.lights))
+	}
+
+	fn same_image(a: &Framebuffer, b: &Framebuffer) -> bool {
+		(0..a.height()).all(|y| (0..a.width()).all(|x| {
+			let (sum_a, sum_b) = (a.sum(x, y), b.sum(x, y));
+			[sum_a.x, sum_a.y, sum_a.z].map(f64::to_bits) == [sum_b.x, sum_b.y, sum_b.z].map(f64::to_bits)
+				&& a.sample_count(x, y) == b.sample_count(x, y)
+		}))
+	}
+
+	// Root mean square difference of the pixels of two images
+	fn rmse(image: &Framebuffer, reference: &Framebuffer) -> f64 {
+		let mut squared_error: f64 = 0.0;
+		for y in 0..image.height() {
+			for x in 0..image.width() {
+				squared_error += (image.pixel(x, y) - reference.pixel(x, y)).length_squared();
+			}
+		}
+		(squared_error / (image.width() * image.height()) as f64).sqrt()
+	}
+
+	#[test]
+	fn render_does_not_depend_on_thread_count() {
+		let scene: Scene = parse_scene(SCENE, Path::new("")).expect("test scene");
+		let camera: Camera = scene.camera.build();
+
+		let single: Framebuffer = render_in_pool(&scene, &camera, 1);
+		for threads in [2, 7] {
+			assert!(same_image(&single, &render_in_pool(&scene, &camera, threads)), "{} threads changed the image", threads);
+		}
+	}
+
+	#[test]
+	fn seed_alone_picks_the_image() {
+		let scene: Scene = parse_scene(SCENE, Path::new("")).expect("test scene");
+		let mut camera: Camera = scene.camera.build();
+		camera.set_seed(42);
+		let image: Framebuffer = render_in_pool(&scene, &camera, 1);
+		assert!(same_image(&image, &render_in_pool(&scene, &camera, 5)), "same seed rendered different images");
+
+		camera.set_seed(43);
+		assert!(!same_image(&image, &render_in_pool(&scene, &camera, 5)), "another seed rendered the same image");
+	}
+
+	#[test]
+	fn samplers_beat_independent_at_equal_samples() {
+		let scene: Scene = parse_scene(LIT_SCENE, Path::new("")).expect("test scene");
+		let render = |kind: SamplerKind, samples_per_pixel: u32, seed: u64| -> Framebuffer {
+			let mut settings: CameraSettings = scene.camera.clone();
+			settings.sampler = kind;
+			settings.samples_per_pixel = samples_per_pixel;
+			settings.seed = seed;
+			render_in_pool(&scene, &settings.build(), 4)
+		};
+
+		// The reference has its own seed, so its noise is unrelated to the renders
+		let reference: Framebuffer = render(SamplerKind::Independent, 4096, 1);
+		let independent: f64 = rmse(&render(SamplerKind::Independent, 16, 0), &reference);
+		for kind in [SamplerKind::Stratified, SamplerKind::Halton, SamplerKind::Sobol] {
+			let error: f64 = rmse(&render(kind, 16, 0), &reference);
+			assert!(error < independent, "{} is not better than independent", kind);
+		}
+	}
+}
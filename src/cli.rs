@@ -0,0 +1,189 @@
+use std::path::PathBuf;
+
+use clap::{Args, Parser, Subcommand};
+
+use ozopathtracer_rs::{Background, CameraSettings, SamplerKind, Vec3, Point3};
+
+#[derive(Parser)]
+#[command(version, about = "Path tracer from \"Ray Tracing in One Weekend\"")]
+pub struct Cli {
+	#[command(subcommand)]
+	pub command: Command,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+	/// Render a scene to an image file
+	Render(RenderArgs),
+	/// Print scene statistics without rendering
+	Info(InfoArgs),
+}
+
+#[derive(Args)]
+pub struct RenderArgs {
+	/// Scene file. The random spheres scene is rendered when omitted
+	pub scene: Option<PathBuf>,
+
+	/// Model file (obj, gltf, glb, ply or stl) to render in a default studio scene
+	#[arg(long, conflicts_with = "scene")]
+	pub mesh: Option<PathBuf>,
+
+	/// Output image path. The extension picks the format: png, exr, hdr or pfm
+	#[arg(short, long, default_value = "test.png")]
+	pub output: PathBuf,
+
+	/// Write OpenEXR channels as half floats instead of full floats
+	#[arg(long)]
+	pub half: bool,
+
+	/// Number of render threads. All cores are used when omitted
+	#[arg(short = 'j', long, value_parser = clap::value_parser!(u32).range(1..))]
+	pub threads: Option<u32>,
+
+	/// Background overriding the one of the scene: none, gradient or a R,G,B color
+	#[arg(long, value_parser = parse_background)]
+	pub background: Option<Background>,
+
+	#[command(flatten)]
+	pub camera: CameraArgs,
+}
+
+#[derive(Args)]
+pub struct InfoArgs {
+	/// Scene file. The random spheres scene is described when omitted
+	pub scene: Option<PathBuf>,
+
+	/// Model file (obj, gltf, glb, ply or stl) to describe in a default studio scene
+	#[arg(long, conflicts_with = "scene")]
+	pub mesh: Option<PathBuf>,
+
+	#[command(flatten)]
+	pub camera: CameraArgs,
+}
+
+// Camera settings overriding the ones of the scene
+#[derive(Args)]
+pub struct CameraArgs {
+	/// Rendered image width in pixel count
+	#[arg(short, long, value_parser = clap::value_parser!(u32).range(1..))]
+	pub width: Option<u32>,
+
+	/// Ratio of image width over height, as a number or as W:H
+	#[arg(short, long, value_parser = parse_aspect_ratio)]
+	pub aspect_ratio: Option<f64>,
+
+	/// Count of random samples for each pixel
+	#[arg(short, long, value_parser = clap::value_parser!(u32).range(1..))]
+	pub spp: Option<u32>,
+
+	/// Maximum number of ray bounces into scene
+	#[arg(short = 'd', long, value_parser = clap::value_parser!(u32).range(1..))]
+	pub max_depth: Option<u32>,
+
+	/// Vertical field of view in degrees
+	#[arg(long, value_parser = parse_vfov)]
+	pub vfov: Option<f64>,
+
+	/// Point camera is looking from, as X,Y,Z
+	#[arg(long, value_parser = parse_vec3, allow_hyphen_values = true)]
+	pub look_from: Option<Point3>,
+
+	/// Point camera is looking at, as X,Y,Z
+	#[arg(long, value_parser = parse_vec3, allow_hyphen_values = true)]
+	pub look_at: Option<Point3>,
+
+	/// Camera-relative "up" direction, as X,Y,Z
+	#[arg(long, value_parser = parse_vec3, allow_hyphen_values = true)]
+	pub vup: Option<Vec3>,
+
+	/// Variation angle of rays through each pixel in degrees. Zero disables depth of field
+	#[arg(long, value_parser = parse_non_negative)]
+	pub defocus_angle: Option<f64>,
+
+	/// Distance from the camera to the plane of perfect focus
+	#[arg(long, value_parser = parse_positive)]
+	pub focus_dist: Option<f64>,
+
+	/// Seed of the random generators
+	#[arg(long)]
+	pub seed: Option<u64>,
+
+	/// Distribution of the samples of each pixel: independent, stratified, halton or sobol
+	#[arg(long)]
+	pub sampler: Option<SamplerKind>,
+}
+
+impl CameraArgs {
+	pub fn apply(&self, settings: &mut CameraSettings) {
+		if let Some(width) = self.width { settings.image_width = width; }
+		if let Some(aspect_ratio) = self.aspect_ratio { settings.aspect_ratio = aspect_ratio; }
+		if let Some(spp) = self.spp { settings.samples_per_pixel = spp; }
+		if let Some(max_depth) = self.max_depth { settings.max_depth = max_depth; }
+		if let Some(vfov) = self.vfov { settings.vfov = vfov; }
+		if let Some(look_from) = self.look_from { settings.look_from = look_from; }
+		if let Some(look_at) = self.look_at { settings.look_at = look_at; }
+		if let Some(vup) = self.vup { settings.vup = vup; }
+		if let Some(defocus_angle) = self.defocus_angle { settings.defocus_angle = defocus_angle; }
+		if let Some(focus_dist) = self.focus_dist { settings.focus_dist = focus_dist; }
+		if let Some(seed) = self.seed { settings.seed = seed; }
+		if let Some(sampler) = self.sampler { settings.sampler = sampler; }
+	}
+}
+
+fn parse_f64(value: &str) -> Result<f64, String> {
+	value.trim().parse::<f64>()
+		.map_err(|_| format!("'{}' is not a number", value))
+}
+
+fn parse_positive(value: &str) -> Result<f64, String> {
+	let number: f64 = parse_f64(value)?;
+	if number > 0.0 && number.is_finite() {
+		Ok(number)
+	} else {
+		Err(format!("{} must be greater than zero", number))
+	}
+}
+
+fn parse_non_negative(value: &str) -> Result<f64, String> {
+	let number: f64 = parse_f64(value)?;
+	if number >= 0.0 && number.is_finite() {
+		Ok(number)
+	} else {
+		Err(format!("{} must not be negative", number))
+	}
+}
+
+fn parse_vfov(value: &str) -> Result<f64, String> {
+	let vfov: f64 = parse_f64(value)?;
+	if vfov > 0.0 && vfov < 180.0 {
+		Ok(vfov)
+	} else {
+		Err(format!("{} is outside of the (0, 180) degrees range", vfov))
+	}
+}
+
+fn parse_aspect_ratio(value: &str) -> Result<f64, String> {
+	match value.split_once(':') {
+		Some((width, height)) => Ok(parse_positive(width)? / parse_positive(height)?),
+		None => parse_positive(value)
+	}
+}
+
+fn parse_vec3(value: &str) -> Result<Vec3, String> {
+	let coordinates: Vec<f64> = value.split(',')
+		.map(parse_f64)
+		.collect::<Result<Vec<f64>, String>>()?;
+
+	match coordinates[..] {
+		[x, y, z] => Ok(Vec3::new(x, y, z)),
+		_ => Err(format!("expected three comma separated numbers, found {}", coordinates.len()))
+	}
+}
+
+fn parse_background(value: &str) -> Result<Background, String> {
+	match value {
+		"none" => Ok(Background::None),
+		"gradient" => Ok(Background::default()),
+		_ => parse_vec3(value).map(|color| Background::Solid { color })
+	}
+}
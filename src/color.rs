@@ -3,4 +3,53 @@ use crate::vec::Vec3;
 pub type Color = Vec3;
 
 pub const WHITE : Color = Color::ones();
-pub const BLACK : Color = Color::zeros();
\ No newline at end of file
+pub const BLACK : Color = Color::zeros();
+
+/// Linear value of an sRGB encoded one, both in [0, 1]
+pub fn srgb_to_linear(value: f64) -> f64 {
+	if value <= 0.04045 { value / 12.92 } else { ((value + 0.055) / 1.055).powf(2.4) }
+}
+
+/// Perceived brightness of a linear color, with the Rec. 709 weights
+pub fn luminance(color: Color) -> f64 {
+	0.2126 * color.x + 0.7152 * color.y + 0.0722 * color.z
+}
+
+/// Linear color of the light of a black body at a temperature in Kelvin, deep red
+/// below 1000 K up to bluish white above 10000 K. The brightness grows with the fourth
+/// power of the temperature, as the radiated power does, and is 1 at 1000 K
+pub fn blackbody(temperature: f64) -> Color {
+	if temperature <= 0.0 {
+		return BLACK;
+	}
+
+	// Planck's law weighted by the analytic fits of the CIE matching functions of
+	// Wyman, Sloan and Shirley (2013), over the visible wavelengths in nm
+	let lobe = |lambda: f64, mean: f64, below: f64, above: f64| -> f64 {
+		let t: f64 = (lambda - mean) / if lambda < mean { below } else { above };
+		(-0.5 * t * t).exp()
+	};
+	let mut xyz: Vec3 = Vec3::zeros();
+	for step in 0..=94 {
+		let lambda: f64 = 360.0 + 5.0 * step as f64;
+		let meters: f64 = lambda * 1e-9;
+		let radiance: f64 = 1.0 / (meters.powi(5) * ((0.014387769 / (meters * temperature)).exp_m1()));
+		xyz += radiance * Vec3::new(
+			1.056 * lobe(lambda, 599.8, 37.9, 31.0) + 0.362 * lobe(lambda, 442.0, 16.0, 26.7) - 0.065 * lobe(lambda, 501.1, 20.4, 26.2),
+			0.821 * lobe(lambda, 568.8, 46.9, 40.5) + 0.286 * lobe(lambda, 530.9, 16.3, 31.1),
+			1.217 * lobe(lambda, 437.0, 11.8, 36.0) + 0.681 * lobe(lambda, 459.0, 26.0, 13.8)
+		);
+	}
+	if xyz.y <= 0.0 || !xyz.y.is_finite() {
+		return BLACK;
+	}
+
+	// XYZ to linear sRGB, the colors out of its gamut clamped
+	let xyz: Vec3 = xyz / xyz.y;
+	let rgb: Color = Color::new(
+		(3.2406 * xyz.x - 1.5372 * xyz.y - 0.4986 * xyz.z).max(0.0),
+		(-0.9689 * xyz.x + 1.8758 * xyz.y + 0.0415 * xyz.z).max(0.0),
+		(0.0557 * xyz.x - 0.2040 * xyz.y + 1.0570 * xyz.z).max(0.0)
+	);
+	((temperature / 1000.0).powi(4) / luminance(rgb)) * rgb
+}
@@ -0,0 +1,75 @@
+use std::sync::Arc;
+
+use crate::{
+	aabb::Aabb,
+	color::{Color, WHITE},
+	hit::{HitRecord, Hitable},
+	material::{Material, Isotropic},
+	ray::Ray,
+	sampler::Sampler,
+	vec::Vec3
+};
+
+/// Participating medium of constant density filling a boundary shape, like smoke or
+/// fog. Rays travel through it until they scatter at a random distance, the denser
+/// the medium the sooner, with its phase function as material. The boundary must be
+/// closed and convex: a ray is inside between its first two crossings
+pub struct ConstantMedium {
+	boundary: Arc<dyn Hitable>,
+	neg_inv_density: f64,
+	phase_function: Arc<dyn Material>,
+}
+
+impl ConstantMedium {
+	/// Medium scattering light equally in every direction, keeping the albedo of it
+	pub fn new(boundary: Arc<dyn Hitable>, density: f64, albedo: Color) -> ConstantMedium {
+		ConstantMedium::from_phase_function(boundary, density, Arc::new(Isotropic::new(albedo)))
+	}
+
+	/// Medium scattering light with a given phase function, such as `Isotropic` or
+	/// `HenyeyGreenstein`
+	pub fn from_phase_function(boundary: Arc<dyn Hitable>, density: f64, phase_function: Arc<dyn Material>) -> ConstantMedium {
+		ConstantMedium { boundary, neg_inv_density: -1.0 / density, phase_function }
+	}
+}
+
+impl Hitable for ConstantMedium {
+	fn hit(&self, ray: &Ray, ray_tmin: f64, ray_tmax: f64, sampler: &mut dyn Sampler) -> Option<HitRecord> {
+		// Crossings of the boundary, also behind the origin for rays starting inside
+		let entry: HitRecord = self.boundary.hit(ray, f64::NEG_INFINITY, f64::INFINITY, sampler)?;
+		let exit: HitRecord = self.boundary.hit(ray, entry.t + 0.0001, f64::INFINITY, sampler)?;
+
+		let t_entry: f64 = entry.t.max(ray_tmin).max(0.0);
+		let t_exit: f64 = exit.t.min(ray_tmax);
+		if t_entry >= t_exit {
+			return None;
+		}
+
+		// Free-flight distance, exponentially distributed
+		let ray_length: f64 = ray.dir().length();
+		let distance_inside: f64 = (t_exit - t_entry) * ray_length;
+		let hit_distance: f64 = self.neg_inv_density * (1.0 - sampler.get_1d()).ln();
+		if hit_distance > distance_inside {
+			return None;
+		}
+
+		let t: f64 = t_entry + hit_distance / ray_length;
+		Some(HitRecord {
+			p: ray.at(t),
+			normal: Vec3::new(1.0, 0.0, 0.0),	// Arbitrary, the phase function ignores it
+			t,
+			u: 0.0,
+			v: 0.0,
+			dpdu: Vec3::zeros(),
+			dpdv: Vec3::zeros(),
+			uv_footprint: 0.0,
+			front_face: true,
+			color: WHITE,
+			material: self.phase_function.clone()
+		})
+	}
+
+	fn bounding_box(&self) -> Aabb {
+		self.boundary.bounding_box()
+	}
+}
@@ -0,0 +1,92 @@
+use image::{ImageBuffer, Rgb, Rgb32FImage};
+use rayon::prelude::*;
+
+use crate::color::{Color, BLACK};
+
+/// Linear radiance accumulated by the renderer. Each pixel keeps the sum of its
+/// samples and how many of them were taken, so several passes can be merged
+/// before the average is resolved.
+#[derive(Clone)]
+pub struct Framebuffer {
+	width: u32,
+	height: u32,
+	sums: Vec<Color>,			// Sum of the radiance samples of each pixel
+	sample_counts: Vec<u32>,	// Count of samples added to each pixel
+}
+
+impl Framebuffer {
+	pub fn new(width: u32, height: u32) -> Framebuffer {
+		let len: usize = width as usize * height as usize;
+		Framebuffer {
+			width,
+			height,
+			sums: vec![BLACK; len],
+			sample_counts: vec![0; len]
+		}
+	}
+
+	pub fn width(&self) -> u32 {
+		self.width
+	}
+
+	pub fn height(&self) -> u32 {
+		self.height
+	}
+
+	pub fn add_sample(&mut self, x: u32, y: u32, color: Color) {
+		let index: usize = self.index(x, y);
+		self.sums[index] += color;
+		self.sample_counts[index] += 1;
+	}
+
+	pub fn sum(&self, x: u32, y: u32) -> Color {
+		self.sums[self.index(x, y)]
+	}
+
+	pub fn sample_count(&self, x: u32, y: u32) -> u32 {
+		self.sample_counts[self.index(x, y)]
+	}
+
+	/// Average of the samples of a pixel, black if it has none
+	pub fn pixel(&self, x: u32, y: u32) -> Color {
+		let index: usize = self.index(x, y);
+		match self.sample_counts[index] {
+			0 => BLACK,
+			count => self.sums[index] / count as f64
+		}
+	}
+
+	/// Adds the samples of another framebuffer of the same size, as if they had
+	/// been rendered into this one
+	pub fn merge(&mut self, other: &Framebuffer) {
+		assert!(self.width == other.width && self.height == other.height, 
+			"Merging framebuffers of different sizes");
+
+		for (sum, other_sum) in self.sums.iter_mut().zip(other.sums.iter()) {
+			*sum += *other_sum;
+		}
+		for (count, other_count) in self.sample_counts.iter_mut().zip(other.sample_counts.iter()) {
+			*count += *other_count;
+		}
+	}
+
+	/// Per-pixel averages as a linear floating-point image
+	pub fn resolve(&self) -> Rgb32FImage {
+		ImageBuffer::from_fn(self.width, self.height, |x, y| {
+			let color: Color = self.pixel(x, y);
+			Rgb([color.x as f32, color.y as f32, color.z as f32])
+		})
+	}
+
+	/// Parallel mutable access to every row as (row index, (sums, sample counts))
+	pub(crate) fn par_rows_mut(&mut self) -> impl IndexedParallelIterator<Item = (usize, (&mut [Color], &mut [u32]))> {
+		let width: usize = self.width as usize;
+		self.sums.par_chunks_mut(width)
+			.zip(self.sample_counts.par_chunks_mut(width))
+			.enumerate()
+	}
+
+	fn index(&self, x: u32, y: u32) -> usize {
+		y as usize * self.width as usize + x as usize
+	}
+}
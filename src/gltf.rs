@@ -0,0 +1,442 @@
+use std::{
+	collections::HashMap,
+	fmt,
+	path::{Path, PathBuf},
+	sync::Arc
+};
+
+use ::image::Rgb32FImage;
+use ::gltf::{
+	buffer,
+	camera::Projection,
+	image::{self, Format},
+	mesh::Mode,
+	texture,
+	Document, Node
+};
+
+use crate::{
+	camera::CameraSettings,
+	color::{Color, srgb_to_linear},
+	material::{Material, Principled},
+	mesh::TriangleMesh,
+	texture::{Texture, SolidColor, ImageTexture, WrapMode},
+	triangle::Uv,
+	vec::{Mat4, Point3, Vec3}
+};
+
+/// Viewpoint of a camera node, in the terms of the renderer camera
+pub struct GltfCamera {
+	pub vfov: f64,
+	pub aspect_ratio: Option<f64>,
+	pub look_from: Point3,
+	pub look_at: Point3,
+	pub vup: Vec3,
+}
+
+impl GltfCamera {
+	/// Replace the viewpoint of the settings. glTF cameras are pinholes, so depth
+	/// of field is disabled
+	pub fn apply(&self, settings: &mut CameraSettings) {
+		settings.vfov = self.vfov;
+		if let Some(aspect_ratio) = self.aspect_ratio {
+			settings.aspect_ratio = aspect_ratio;
+		}
+		settings.look_from = self.look_from;
+		settings.look_at = self.look_at;
+		settings.vup = self.vup;
+		settings.defocus_angle = 0.0;
+	}
+
+	/// Same camera moved along with the scene by the transform
+	pub fn transformed(&self, transform: &Mat4) -> GltfCamera {
+		GltfCamera {
+			vfov: self.vfov,
+			aspect_ratio: self.aspect_ratio,
+			look_from: transform.transform_point(self.look_from),
+			look_at: transform.transform_point(self.look_at),
+			vup: transform.transform_vector(self.vup)
+		}
+	}
+}
+
+/// Meshes of the default scene of a glTF file, already placed in world space,
+/// with its first camera and the warnings about what was not imported
+pub struct GltfModel {
+	pub meshes: Vec<TriangleMesh>,
+	pub camera: Option<GltfCamera>,
+	pub warnings: Vec<String>,
+}
+
+#[derive(Debug)]
+pub enum GltfError {
+	Import(PathBuf, ::gltf::Error),
+	NoScene(PathBuf),
+}
+
+impl fmt::Display for GltfError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			GltfError::Import(path, err) => write!(f, "{}: {}", path.display(), err),
+			GltfError::NoScene(path) => write!(f, "{}: no scene to import", path.display())
+		}
+	}
+}
+
+impl std::error::Error for GltfError {}
+
+struct Importer<'a> {
+	path: &'a Path,
+	buffers: &'a [buffer::Data],
+	images: &'a [image::Data],
+	materials: HashMap<Option<usize>, Arc<dyn Material>>,
+	meshes: Vec<TriangleMesh>,
+	camera: Option<GltfCamera>,
+	warnings: Vec<String>,
+}
+
+/// Import a `.gltf` file with its buffers and images, or a binary `.glb` file
+pub fn load_gltf(path: &Path) -> Result<GltfModel, GltfError> {
+	let (document, buffers, images) = ::gltf::import(path)
+		.map_err(|err| GltfError::Import(path.to_path_buf(), err))?;
+
+	let scene = document.default_scene()
+		.or_else(|| document.scenes().next())
+		.ok_or_else(|| GltfError::NoScene(path.to_path_buf()))?;
+
+	let mut importer: Importer = Importer {
+		path,
+		buffers: &buffers,
+		images: &images,
+		materials: HashMap::new(),
+		meshes: Vec::new(),
+		camera: None,
+		warnings: Vec::new()
+	};
+
+	for node in scene.nodes() {
+		importer.import_node(&node, &Mat4::identity());
+	}
+	importer.warn_unused_cameras(&document);
+
+	Ok(GltfModel {
+		meshes: importer.meshes,
+		camera: importer.camera,
+		warnings: importer.warnings
+	})
+}
+
+impl Importer<'_> {
+	fn warn(&mut self, message: String) {
+		self.warnings.push(format!("{}: {}", self.path.display(), message));
+	}
+
+	fn import_node(&mut self, node: &Node, parent: &Mat4) {
+		let local: Mat4 = Mat4::from_columns(node.transform().matrix().map(|column| column.map(|x| x as f64)));
+		let transform: Mat4 = *parent * local;
+		let name: String = node.name().map_or_else(|| format!("#{}", node.index()), str::to_string);
+
+		if let Some(mesh) = node.mesh() {
+			if node.skin().is_some() || mesh.weights().is_some() {
+				self.warn(format!("node {}: skinning and morph targets ignored, rest pose imported", name));
+			}
+			for primitive in mesh.primitives() {
+				self.import_primitive(&name, &primitive, &transform);
+			}
+		}
+
+		if let Some(camera) = node.camera() {
+			match camera.projection() {
+				Projection::Perspective(perspective) if self.camera.is_none() => {
+					self.camera = Some(GltfCamera {
+						vfov: (perspective.yfov() as f64).to_degrees(),
+						aspect_ratio: perspective.aspect_ratio().map(|ratio| ratio as f64),
+						look_from: transform.transform_point(Point3::zeros()),
+						// Cameras look down their -Z axis with +Y up
+						look_at: transform.transform_point(Point3::new(0.0, 0.0, -1.0)),
+						vup: transform.transform_vector(Vec3::new(0.0, 1.0, 0.0))
+					});
+				},
+				Projection::Perspective(_) => {},
+				Projection::Orthographic(_) =>
+					self.warn(format!("node {}: orthographic cameras are not supported", name))
+			}
+		}
+
+		if node.light().is_some() {
+			self.warn(format!("node {}: punctual lights are not supported, use emissive materials", name));
+		}
+
+		for child in node.children() {
+			self.import_node(&child, &transform);
+		}
+	}
+
+	fn import_primitive(&mut self, name: &str, primitive: &::gltf::Primitive, transform: &Mat4) {
+		if primitive.mode() != Mode::Triangles {
+			self.warn(format!("node {}: {:?} primitive ignored, only triangles are supported", name, primitive.mode()));
+			return;
+		}
+
+		let reader = primitive.reader(|buffer| self.buffers.get(buffer.index()).map(|data| &data.0[..]));
+		let positions: Vec<Point3> = match reader.read_positions() {
+			Some(positions) => positions.map(|p| transform.transform_point(vec3(p))).collect(),
+			None => {
+				self.warn(format!("node {}: primitive without positions ignored", name));
+				return;
+			}
+		};
+
+		let indices: Vec<u32> = match reader.read_indices() {
+			Some(indices) => indices.into_u32().collect(),
+			None => (0..positions.len() as u32).collect()
+		};
+		let triangles: Vec<[u32; 3]> = indices.chunks_exact(3)
+			.map(|triangle| [triangle[0], triangle[1], triangle[2]])
+			.collect();
+
+		// Inverse transpose, keeping normals perpendicular to surfaces under non-uniform scaling
+		let normal_transform: Mat4 = transform.inverse().unwrap_or_default().transpose();
+		let normals: Option<Vec<Vec3>> = reader.read_normals()
+			.map(|normals| normals.map(|n| normal_transform.transform_vector(vec3(n)).unit_vector()).collect());
+		// Texture coordinates start at the top left corner of images, v going down
+		let uvs: Option<Vec<Uv>> = reader.read_tex_coords(0)
+			.map(|uvs| uvs.into_f32().map(|[u, v]| [u as f64, 1.0 - v as f64] as Uv).collect());
+
+		let material: Arc<dyn Material> = self.material(&primitive.material());
+		let mesh: Result<TriangleMesh, String> = TriangleMesh::new(positions, triangles, material)
+			.and_then(|mesh| match normals {
+				Some(normals) => mesh.with_normals(normals),
+				None => Ok(mesh)
+			})
+			.and_then(|mesh| match uvs {
+				Some(uvs) => mesh.with_uvs(uvs),
+				None => Ok(mesh)
+			});
+		match mesh {
+			Ok(mesh) => self.meshes.push(mesh),
+			Err(err) => self.warn(format!("node {}: primitive ignored, {}", name, err))
+		}
+	}
+
+	// Principled material of a metallic-roughness one. Base color, metallic, roughness,
+	// transmission and emissive textures feed the matching parameters, with the
+	// factors of the material baked in. Emission is added to the reflected light
+	fn material(&mut self, material: &::gltf::Material) -> Arc<dyn Material> {
+		if let Some(built) = self.materials.get(&material.index()) {
+			return built.clone();
+		}
+
+		let name: String = material.name().map_or_else(
+			|| material.index().map_or("default".to_string(), |index| format!("#{}", index)),
+			str::to_string
+		);
+		let pbr = material.pbr_metallic_roughness();
+
+		// Textures wrapped as their sampler says, or the plain factor without any
+		let images: &[image::Data] = self.images;
+		let texture = |info: Option<texture::Info>, factor: Color, build: &dyn Fn(&image::Data) -> ImageTexture| -> Arc<dyn Texture> {
+			let info: texture::Info = match info {
+				Some(info) => info,
+				None => return Arc::new(SolidColor::new(factor))
+			};
+			match images.get(info.texture().source().index()) {
+				Some(image) => {
+					let sampler = info.texture().sampler();
+					Arc::new(build(image).with_wrap(wrap_mode(sampler.wrap_s()), wrap_mode(sampler.wrap_t())))
+				},
+				None => Arc::new(SolidColor::new(factor))
+			}
+		};
+		let grey = |value: f64| Color::new(value, value, value);
+
+		let base_factor: [f32; 4] = pbr.base_color_factor();
+		let base_factor: Color = Color::new(base_factor[0] as f64, base_factor[1] as f64, base_factor[2] as f64);
+		let base_color: Arc<dyn Texture> = texture(pbr.base_color_texture(), base_factor, &|image| color_texture(image, base_factor));
+
+		// Roughness is stored in the green channel and metalness in the blue one
+		let metallic_factor: f64 = pbr.metallic_factor() as f64;
+		let roughness_factor: f64 = pbr.roughness_factor() as f64;
+		let metallic: Arc<dyn Texture> = texture(
+			pbr.metallic_roughness_texture(),
+			grey(metallic_factor),
+			&|image| channel_texture(image, 2, metallic_factor)
+		);
+		let roughness: Arc<dyn Texture> = texture(
+			pbr.metallic_roughness_texture(),
+			grey(roughness_factor),
+			&|image| channel_texture(image, 1, roughness_factor)
+		);
+
+		let mut principled: Principled = Principled::new(base_color)
+			.with_metallic(metallic)
+			.with_roughness(roughness);
+
+		// Transmission is stored in the red channel
+		if let Some(transmission) = material.transmission() {
+			let factor: f64 = transmission.transmission_factor() as f64;
+			principled = principled.with_transmission(texture(
+				transmission.transmission_texture(),
+				grey(factor),
+				&|image| channel_texture(image, 0, factor)
+			));
+		}
+		if let Some(ior) = material.ior() {
+			principled = principled.with_ior(Arc::new(SolidColor::new(grey(ior as f64))));
+		}
+
+		let emissive_factor: [f32; 3] = material.emissive_factor();
+		let emissive_strength: f64 = material.emissive_strength().unwrap_or(1.0) as f64;
+		let emissive_color: Color = emissive_strength * Color::new(
+			emissive_factor[0] as f64,
+			emissive_factor[1] as f64,
+			emissive_factor[2] as f64
+		);
+		if emissive_color.x.max(emissive_color.y).max(emissive_color.z) > 0.0 {
+			principled = principled.with_emission(texture(
+				material.emissive_texture(),
+				emissive_color,
+				&|image| color_texture(image, emissive_color)
+			));
+		}
+
+		if material.normal_texture().is_some() || material.occlusion_texture().is_some() {
+			self.warn(format!("material {}: normal and occlusion textures ignored", name));
+		}
+
+		let built: Arc<dyn Material> = Arc::new(principled);
+		self.materials.insert(material.index(), built.clone());
+		built
+	}
+
+	fn warn_unused_cameras(&mut self, document: &Document) {
+		let camera_count: usize = document.cameras().count();
+		if camera_count > 1 && self.camera.is_some() {
+			self.warn(format!("{} cameras defined, the first one of the scene is used", camera_count));
+		}
+	}
+}
+
+fn vec3(v: [f32; 3]) -> Vec3 {
+	Vec3::new(v[0] as f64, v[1] as f64, v[2] as f64)
+}
+
+// Texels of the image as linear RGBA. Single channel images are gray
+fn texels(image: &image::Data, srgb: bool) -> Vec<[f64; 4]> {
+	let (channels, bytes): (usize, usize) = match image.format {
+		Format::R8 => (1, 1),
+		Format::R8G8 => (2, 1),
+		Format::R8G8B8 => (3, 1),
+		Format::R8G8B8A8 => (4, 1),
+		Format::R16 => (1, 2),
+		Format::R16G16 => (2, 2),
+		Format::R16G16B16 => (3, 2),
+		Format::R16G16B16A16 => (4, 2),
+		Format::R32G32B32FLOAT => (3, 4),
+		Format::R32G32B32A32FLOAT => (4, 4)
+	};
+
+	let read = |bytes_of_value: &[u8]| -> f64 {
+		match bytes_of_value.len() {
+			1 => bytes_of_value[0] as f64 / 255.0,
+			2 => u16::from_le_bytes([bytes_of_value[0], bytes_of_value[1]]) as f64 / 65535.0,
+			_ => f32::from_le_bytes([bytes_of_value[0], bytes_of_value[1], bytes_of_value[2], bytes_of_value[3]]) as f64
+		}
+	};
+	// Float images are already linear
+	let decode = |value: f64| -> f64 {
+		if srgb && bytes < 4 { srgb_to_linear(value) } else { value }
+	};
+
+	image.pixels.chunks_exact(channels * bytes)
+		.map(|texel| {
+			let values: Vec<f64> = texel.chunks_exact(bytes).map(read).collect();
+			match values[..] {
+				[gray] => [decode(gray), decode(gray), decode(gray), 1.0],
+				[gray, alpha] => [decode(gray), decode(gray), decode(gray), alpha],
+				[r, g, b] => [decode(r), decode(g), decode(b), 1.0],
+				[r, g, b, a, ..] => [decode(r), decode(g), decode(b), a],
+				_ => [1.0; 4]
+			}
+		})
+		.collect()
+}
+
+// Color texture of an sRGB image, scaled by the factor of the material. Alpha is dropped
+fn color_texture(image: &image::Data, factor: Color) -> ImageTexture {
+	let pixels: Vec<f32> = texels(image, true).iter()
+		.flat_map(|[r, g, b, _]| [(r * factor.x) as f32, (g * factor.y) as f32, (b * factor.z) as f32])
+		.collect();
+	ImageTexture::new(Rgb32FImage::from_raw(image.width, image.height, pixels).unwrap_or_default())
+}
+
+// Grey texture of one linear channel of an image, scaled by the factor of the material
+fn channel_texture(image: &image::Data, channel: usize, factor: f64) -> ImageTexture {
+	let pixels: Vec<f32> = texels(image, false).iter()
+		.flat_map(|texel| [(texel[channel] * factor) as f32; 3])
+		.collect();
+	ImageTexture::new(Rgb32FImage::from_raw(image.width, image.height, pixels).unwrap_or_default())
+}
+
+fn wrap_mode(mode: texture::WrappingMode) -> WrapMode {
+	match mode {
+		texture::WrappingMode::ClampToEdge => WrapMode::Clamp,
+		texture::WrappingMode::MirroredRepeat => WrapMode::Mirror,
+		texture::WrappingMode::Repeat => WrapMode::Repeat
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{
+		hit::{HitRecord, Hitable},
+		ray::Ray,
+		sampler::{Sampler, SamplerKind}
+	};
+	use std::fs;
+
+	#[test]
+	fn emissive_materials_still_reflect() {
+		// Triangle facing +Z, its buffer in a file next to the glTF one
+		let directory: PathBuf = std::env::temp_dir();
+		let name: String = format!("ozopathtracer_emissive_{}", std::process::id());
+		let positions: Vec<u8> = [0.0_f32, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0].iter()
+			.flat_map(|value| value.to_le_bytes())
+			.collect();
+		fs::write(directory.join(format!("{}.bin", name)), positions).expect("temporary buffer file");
+		let path: PathBuf = directory.join(format!("{}.gltf", name));
+		fs::write(&path, format!(r#"{{
+			"asset": {{ "version": "2.0" }},
+			"scene": 0,
+			"scenes": [{{ "nodes": [0] }}],
+			"nodes": [{{ "mesh": 0 }}],
+			"meshes": [{{ "primitives": [{{ "attributes": {{ "POSITION": 0 }}, "material": 0 }}] }}],
+			"materials": [{{
+				"pbrMetallicRoughness": {{ "baseColorFactor": [0.8, 0.8, 0.8, 1.0], "metallicFactor": 0.0 }},
+				"emissiveFactor": [1.0, 0.5, 0.25]
+			}}],
+			"buffers": [{{ "uri": "{}.bin", "byteLength": 36 }}],
+			"bufferViews": [{{ "buffer": 0, "byteLength": 36 }}],
+			"accessors": [{{
+				"bufferView": 0, "componentType": 5126, "count": 3, "type": "VEC3",
+				"min": [0.0, 0.0, 0.0], "max": [1.0, 1.0, 0.0]
+			}}]
+		}}"#, name)).expect("temporary glTF file");
+
+		let model = load_gltf(&path);
+		fs::remove_file(&path).ok();
+		fs::remove_file(directory.join(format!("{}.bin", name))).ok();
+		let model: GltfModel = model.expect("glTF file");
+
+		let mut sampler: Box<dyn Sampler> = SamplerKind::Independent.build(1, 1);
+		let ray: Ray = Ray::new(Point3::new(0.25, 0.25, 1.0), Vec3::new(0.0, 0.0, -1.0));
+		let hit: HitRecord = model.meshes[0].hit(&ray, 0.001, f64::INFINITY, sampler.as_mut()).expect("hit on the triangle");
+		let emitted: Color = hit.material.emitted(&ray, &hit);
+		assert_eq!([emitted.x, emitted.y, emitted.z], [1.0, 0.5, 0.25]);
+
+		let reflected: bool = (0..16).any(|_| hit.material.scatter(&ray, &hit, sampler.as_mut())
+			.is_some_and(|interaction| interaction.attenuation().x > 0.0));
+		assert!(reflected, "emissive material does not reflect any light");
+	}
+}
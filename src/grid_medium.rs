@@ -0,0 +1,254 @@
+use std::sync::Arc;
+
+use crate::{
+	aabb::Aabb,
+	color::{Color, BLACK, WHITE, blackbody},
+	hit::{HitRecord, Hitable},
+	material::{Material, MaterialRayInteraction},
+	ray::Ray,
+	sampler::Sampler,
+	vec::{Point3, Vec3},
+	voxel_grid::VoxelGrid
+};
+
+/// Participating medium whose density varies over a box, read from a voxel grid,
+/// like a smoke simulation. Free flights are drawn by delta tracking: tentative
+/// collisions are drawn as in the densest part of the box, and each one is real
+/// in proportion to the density there, which keeps the transmittance unbiased.
+/// Fire emits light from an emission grid, a temperature grid or both
+pub struct GridMedium {
+	bbox: Aabb,
+	density: Arc<VoxelGrid>,
+	density_scale: f64,				// Density of a grid value of 1
+	material: Arc<GridMaterial>,
+}
+
+impl GridMedium {
+	/// Medium filling `bbox`, of density `density_scale` times the grid values, and
+	/// scattering light with a phase function such as `Isotropic` or `HenyeyGreenstein`
+	pub fn new(bbox: Aabb, density: Arc<VoxelGrid>, density_scale: f64, phase_function: Arc<dyn Material>) -> GridMedium {
+		GridMedium {
+			bbox,
+			density,
+			density_scale,
+			material: Arc::new(GridMaterial { bbox, phase_function, emission: None, temperature: None })
+		}
+	}
+
+	/// Radiance emitted by the medium, the grid values times `color`. Where the medium
+	/// is thick enough to hide what is behind, it glows with this radiance, whatever
+	/// the albedo of its phase function
+	pub fn with_emission(mut self, emission: Arc<VoxelGrid>, color: Color) -> GridMedium {
+		let mut material: GridMaterial = (*self.material).clone();
+		material.emission = Some((emission, color));
+		self.material = Arc::new(material);
+		self
+	}
+
+	/// Black body emission of a temperature grid, in Kelvin once multiplied by `scale`,
+	/// with a brightness multiplied by `intensity`
+	pub fn with_temperature(mut self, temperature: Arc<VoxelGrid>, scale: f64, intensity: f64) -> GridMedium {
+		// Planck's law is too slow to integrate at every collision, the colors of the
+		// range of the grid values are tabulated once
+		let max: f64 = temperature.max();
+		let colors: Vec<Color> = (0..BLACKBODY_TABLE_SIZE)
+			.map(|index| intensity * blackbody(scale * max * index as f64 / (BLACKBODY_TABLE_SIZE - 1) as f64))
+			.collect();
+		let mut material: GridMaterial = (*self.material).clone();
+		material.temperature = Some(BlackbodyTable { grid: temperature, max, colors });
+		self.material = Arc::new(material);
+		self
+	}
+
+	// Parameters of the ray entering and leaving the box, None if it misses it
+	fn crossing(&self, ray: &Ray) -> Option<(f64, f64)> {
+		let mut t_enter: f64 = f64::NEG_INFINITY;
+		let mut t_exit: f64 = f64::INFINITY;
+		for axis in 0..3 {
+			let inv_d: f64 = 1.0 / ray.dir()[axis];
+			let t0: f64 = (self.bbox.min[axis] - ray.origin()[axis]) * inv_d;
+			let t1: f64 = (self.bbox.max[axis] - ray.origin()[axis]) * inv_d;
+			t_enter = t_enter.max(t0.min(t1));
+			t_exit = t_exit.min(t0.max(t1));
+		}
+		if t_enter <= t_exit { Some((t_enter, t_exit)) } else { None }
+	}
+}
+
+impl Hitable for GridMedium {
+	fn hit(&self, ray: &Ray, ray_tmin: f64, ray_tmax: f64, sampler: &mut dyn Sampler) -> Option<HitRecord> {
+		let majorant: f64 = self.density_scale * self.density.max();
+		let (t_enter, t_exit) = self.crossing(ray)?;
+		let t_end: f64 = t_exit.min(ray_tmax);
+		if majorant <= 0.0 || t_enter.max(ray_tmin) >= t_end {
+			return None;
+		}
+
+		let ray_length: f64 = ray.dir().length();
+		let mut t: f64 = t_enter.max(ray_tmin);
+		loop {
+			t -= (1.0 - sampler.get_1d()).ln() / (majorant * ray_length);
+			if t >= t_end {
+				return None;
+			}
+
+			let p: Point3 = ray.at(t);
+			let density: f64 = self.density_scale * self.density.lookup(self.material.grid_point(p));
+			if sampler.get_1d() * majorant < density {
+				return Some(HitRecord {
+					p,
+					normal: Vec3::new(1.0, 0.0, 0.0),	// Arbitrary, the phase function ignores it
+					t,
+					u: 0.0,
+					v: 0.0,
+					dpdu: Vec3::zeros(),
+					dpdv: Vec3::zeros(),
+					uv_footprint: 0.0,
+					front_face: true,
+					color: WHITE,
+					material: self.material.clone()
+				});
+			}
+		}
+	}
+
+	fn bounding_box(&self) -> Aabb {
+		self.bbox
+	}
+}
+
+const BLACKBODY_TABLE_SIZE: usize = 1024;	// Tabulated colors of a temperature grid
+
+// Black body colors of a temperature grid, from 0 to its largest value
+#[derive(Clone)]
+struct BlackbodyTable {
+	grid: Arc<VoxelGrid>,
+	max: f64,
+	colors: Vec<Color>,
+}
+
+impl BlackbodyTable {
+	// Linear interpolation of the colors around the grid value at `p`
+	fn lookup(&self, p: Point3) -> Color {
+		if self.max <= 0.0 {
+			return BLACK;
+		}
+		let position: f64 = (self.grid.lookup(p) / self.max).clamp(0.0, 1.0) * (BLACKBODY_TABLE_SIZE - 1) as f64;
+		let index: usize = (position as usize).min(BLACKBODY_TABLE_SIZE - 2);
+		let t: f64 = position - index as f64;
+		(1.0 - t) * self.colors[index] + t * self.colors[index + 1]
+	}
+}
+
+// Phase function of the medium, plus the light emitted where it scatters
+#[derive(Clone)]
+struct GridMaterial {
+	bbox: Aabb,
+	phase_function: Arc<dyn Material>,
+	emission: Option<(Arc<VoxelGrid>, Color)>,
+	temperature: Option<BlackbodyTable>,
+}
+
+impl GridMaterial {
+	// Point of the box mapped to the unit cube of the grids
+	fn grid_point(&self, p: Point3) -> Point3 {
+		let extent: Vec3 = self.bbox.extent();
+		let offset: Vec3 = p - self.bbox.min;
+		Point3::new(offset.x / extent.x, offset.y / extent.y, offset.z / extent.z)
+	}
+}
+
+impl Material for GridMaterial {
+	fn scatter(&self, ray_in: &Ray, hit_record: &HitRecord, sampler: &mut dyn Sampler) -> Option<MaterialRayInteraction> {
+		self.phase_function.scatter(ray_in, hit_record, sampler)
+	}
+
+	fn eval(&self, hit_record: &HitRecord, wi: Vec3, wo: Vec3) -> Color {
+		self.phase_function.eval(hit_record, wi, wo)
+	}
+
+	fn pdf(&self, hit_record: &HitRecord, wi: Vec3, wo: Vec3) -> f64 {
+		self.phase_function.pdf(hit_record, wi, wo)
+	}
+
+	// Only the absorbed share of the collisions emits, the scattered paths carry on
+	// and collect the emission further on. The medium then glows with the emitted
+	// radiance instead of piling it up over the bounces
+	fn emitted(&self, _ray_in: &Ray, hit_record: &HitRecord) -> Color {
+		let p: Point3 = self.grid_point(hit_record.p);
+		let mut emitted: Color = BLACK;
+		if let Some((emission, color)) = &self.emission {
+			emitted += emission.lookup(p).max(0.0) * *color;
+		}
+		if let Some(temperature) = &self.temperature {
+			emitted += temperature.lookup(p);
+		}
+
+		let albedo: Color = self.phase_function.scattering_albedo(hit_record);
+		let absorbed: Color = Color::new((1.0 - albedo.x).max(0.0), (1.0 - albedo.y).max(0.0), (1.0 - albedo.z).max(0.0));
+		absorbed * emitted
+	}
+
+	fn is_volumetric(&self) -> bool {
+		true
+	}
+
+	fn scattering_albedo(&self, hit_record: &HitRecord) -> Color {
+		self.phase_function.scattering_albedo(hit_record)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{
+		background::Background,
+		camera::{Camera, CameraSettings},
+		framebuffer::Framebuffer,
+		light::LightList,
+		material::Isotropic
+	};
+
+	// Mean radiance seen from the center of a thick box of medium glowing with an
+	// emission grid of 0.5 times `color`. The density is uniform, so every collision
+	// is real and few samples are needed
+	fn glow(albedo: f64, color: Color) -> Color {
+		let bbox: Aabb = Aabb::new(Point3::new(-1.0, -1.0, -1.0), Point3::new(1.0, 1.0, 1.0));
+		let density: Arc<VoxelGrid> = Arc::new(VoxelGrid::new([2, 2, 2], vec![1.0; 8]).expect("density grid"));
+		let emission: Arc<VoxelGrid> = Arc::new(VoxelGrid::new([2, 2, 2], vec![0.5; 8]).expect("emission grid"));
+		let medium: GridMedium = GridMedium::new(bbox, density, 100.0, Arc::new(Isotropic::new(Color::new(albedo, albedo, albedo))))
+			.with_emission(emission, color);
+
+		let mut camera: Camera = CameraSettings {
+			image_width: 4,
+			aspect_ratio: 1.0,
+			samples_per_pixel: 4,
+			max_depth: 500,
+			look_from: Point3::zeros(),
+			look_at: Point3::new(0.0, 0.0, -1.0),
+			defocus_angle: 0.0,
+			..CameraSettings::default()
+		}.build();
+		camera.set_background(Background::None);
+		let image: Framebuffer = camera.render(&medium, &LightList::default());
+
+		let mut sum: Color = BLACK;
+		for y in 0..image.height() {
+			for x in 0..image.width() {
+				sum += image.pixel(x, y);
+			}
+		}
+		sum / (image.width() * image.height()) as f64
+	}
+
+	#[test]
+	fn thick_emissive_medium_glows_with_its_emission() {
+		let color: Color = Color::new(2.0, 1.0, 0.5);
+		for albedo in [0.0, 0.5, 0.9] {
+			let radiance: Color = glow(albedo, color);
+			for (value, expected) in [(radiance.x, 1.0), (radiance.y, 0.5), (radiance.z, 0.25)] {
+				assert!((value / expected - 1.0).abs() < 0.05, "albedo {} glows with {} instead of {}", albedo, radiance, 0.5 * color);
+			}
+		}
+	}
+}
@@ -1,46 +1,113 @@
-use std::rc::Rc;
+use std::sync::Arc;
 
 use crate::{
+	aabb::Aabb,
+	color::Color,
 	material::Material,
-	vec::{Vec3, Point3},
-	ray::Ray
+	sampler::Sampler,
+	vec::{Vec3, Point3, dot},
+	ray::{Ray, RayDifferentials}
 };
 
 pub struct HitRecord {
 	pub p: Point3,
 	pub normal: Vec3,
 	pub t: f64,
+	pub u: f64,				// Surface coordinates of the hit point
+	pub v: f64,
+	pub dpdu: Vec3,			// Surface tangents, how the hit point moves with u and v
+	pub dpdv: Vec3,
+	pub uv_footprint: f64,	// Width of the pixel footprint in surface coordinates, 0 when unknown
 	pub front_face: bool,
-	pub material: Rc<dyn Material>
+	pub color: Color,		// Interpolated vertex color, white on surfaces without
+	pub material: Arc<dyn Material>
 }
 
-pub trait Hitable {
-	fn hit(&self, ray: &Ray, ray_tmin: f64, ray_tmax: f64) -> Option<HitRecord>;
+impl HitRecord {
+	/// Footprint of the pixel of `ray` on the surface, from where its differential
+	/// rays cross the tangent plane of the hit. Rays without differentials leave it at 0
+	pub fn set_footprint(&mut self, ray: &Ray) {
+		let differentials: &RayDifferentials = match ray.differentials() {
+			Some(differentials) => differentials,
+			None => return
+		};
+
+		// Offset of the hit point along the differential ray, on the tangent plane
+		let plane_offset = |origin: Point3, dir: Vec3| -> Option<Vec3> {
+			let denominator: f64 = dot(self.normal, dir);
+			if denominator.abs() < 1e-12 {
+				return None;
+			}
+			let t: f64 = dot(self.normal, self.p - origin) / denominator;
+			Some(origin + t * dir - self.p)
+		};
+
+		// Least squares solution of dp = du * dpdu + dv * dpdv
+		let uu: f64 = dot(self.dpdu, self.dpdu);
+		let uv: f64 = dot(self.dpdu, self.dpdv);
+		let vv: f64 = dot(self.dpdv, self.dpdv);
+		let determinant: f64 = uu * vv - uv * uv;
+		if determinant.abs() < 1e-24 {
+			return;
+		}
+		let uv_offset = |dp: Vec3| -> [f64; 2] {
+			let pu: f64 = dot(dp, self.dpdu);
+			let pv: f64 = dot(dp, self.dpdv);
+			[(vv * pu - uv * pv) / determinant, (uu * pv - uv * pu) / determinant]
+		};
+
+		if let (Some(dpdx), Some(dpdy)) = (
+			plane_offset(differentials.x_origin, differentials.x_dir),
+			plane_offset(differentials.y_origin, differentials.y_dir)
+		) {
+			let [dudx, dvdx] = uv_offset(dpdx);
+			let [dudy, dvdy] = uv_offset(dpdy);
+			self.uv_footprint = dudx.abs().max(dvdx.abs()).max(dudy.abs()).max(dvdy.abs());
+		}
+	}
+}
+
+pub trait Hitable: Send + Sync {
+	/// Closest hit in [ray_tmin, ray_tmax]. Objects drawing random numbers, such as
+	/// participating media, take them from `sampler`
+	fn hit(&self, ray: &Ray, ray_tmin: f64, ray_tmax: f64, sampler: &mut dyn Sampler) -> Option<HitRecord>;
+
+	fn bounding_box(&self) -> Aabb;
 }
 
 pub struct HitableList {
-	objects: Vec<Rc<dyn Hitable>>
+	objects: Vec<Arc<dyn Hitable>>,
+	bbox: Aabb,
 }
 
 impl HitableList {
-	pub fn new(objects: Vec<Rc<dyn Hitable>>) -> HitableList {
+	pub fn new(objects: Vec<Arc<dyn Hitable>>) -> HitableList {
+		let bbox: Aabb = objects.iter()
+			.fold(Aabb::empty(), |bbox, object| Aabb::surrounding(&bbox, &object.bounding_box()));
+
 		HitableList { 
-			objects: objects 
+			objects,
+			bbox
 		}
 	}
 
-	pub fn add(&mut self, object: Rc<dyn Hitable>) {
+	pub fn add(&mut self, object: Arc<dyn Hitable>) {
+		self.bbox = Aabb::surrounding(&self.bbox, &object.bounding_box());
 		self.objects.push(object)
 	}
+
+	pub fn objects(&self) -> &[Arc<dyn Hitable>] {
+		&self.objects
+	}
 }
 
 impl Hitable for HitableList {
-	fn hit(&self, ray: &Ray, ray_tmin: f64, ray_tmax: f64) -> Option<HitRecord> {
+	fn hit(&self, ray: &Ray, ray_tmin: f64, ray_tmax: f64, sampler: &mut dyn Sampler) -> Option<HitRecord> {
 		let mut hit_anything: Option<HitRecord> = None;
 		let mut closest_so_far: f64 = ray_tmax;
 
 		for object in self.objects.iter() {
-			if let Some(hit) = object.hit(ray, ray_tmin, closest_so_far) {
+			if let Some(hit) = object.hit(ray, ray_tmin, closest_so_far, sampler) {
 				closest_so_far = hit.t;
 				hit_anything = Some(hit);
 			}
@@ -49,4 +116,7 @@ impl Hitable for HitableList {
 		hit_anything
 	}
 
+	fn bounding_box(&self) -> Aabb {
+		self.bbox
+	}
 }
\ No newline at end of file
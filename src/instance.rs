@@ -0,0 +1,73 @@
+use std::sync::Arc;
+
+use crate::{
+	aabb::Aabb,
+	hit::{HitRecord, Hitable},
+	ray::Ray,
+	sampler::Sampler,
+	vec::{Mat4, Point3}
+};
+
+/// Object placed in the world by an affine transform. The object itself stays in
+/// its own space, so any number of instances can share it
+pub struct Instance {
+	object: Arc<dyn Hitable>,
+	transform: Mat4,			// Object to world space
+	inverse: Mat4,				// World to object space
+	normal_transform: Mat4,		// Inverse transpose, keeps normals perpendicular to surfaces
+	bbox: Aabb,
+}
+
+impl Instance {
+	/// Panics if the transform cannot be inverted
+	pub fn new(object: Arc<dyn Hitable>, transform: Mat4) -> Instance {
+		let inverse: Mat4 = transform.inverse().expect("Instance transform must be invertible");
+
+		// Box around the transformed corners of the object box
+		let object_bbox: Aabb = object.bounding_box();
+		let bbox: Aabb = (0..8)
+			.map(|corner| Point3::new(
+				if corner & 1 == 0 { object_bbox.min.x } else { object_bbox.max.x },
+				if corner & 2 == 0 { object_bbox.min.y } else { object_bbox.max.y },
+				if corner & 4 == 0 { object_bbox.min.z } else { object_bbox.max.z }
+			))
+			.fold(Aabb::empty(), |bbox, corner| bbox.include_point(transform.transform_point(corner)));
+
+		Instance {
+			object,
+			transform,
+			inverse,
+			normal_transform: inverse.transpose(),
+			bbox: if object_bbox.is_empty() { Aabb::empty() } else { bbox }
+		}
+	}
+
+	pub fn object(&self) -> &Arc<dyn Hitable> {
+		&self.object
+	}
+
+	pub fn transform(&self) -> &Mat4 {
+		&self.transform
+	}
+}
+
+impl Hitable for Instance {
+	fn hit(&self, ray: &Ray, ray_tmin: f64, ray_tmax: f64, sampler: &mut dyn Sampler) -> Option<HitRecord> {
+		// The direction is not normalized, so distances along both rays match
+		let object_ray: Ray = Ray::new(
+			self.inverse.transform_point(ray.origin()),
+			self.inverse.transform_vector(ray.dir())
+		);
+
+		let mut hit: HitRecord = self.object.hit(&object_ray, ray_tmin, ray_tmax, sampler)?;
+		hit.p = self.transform.transform_point(hit.p);
+		hit.normal = self.normal_transform.transform_vector(hit.normal).unit_vector();
+		hit.dpdu = self.transform.transform_vector(hit.dpdu);
+		hit.dpdv = self.transform.transform_vector(hit.dpdv);
+		Some(hit)
+	}
+
+	fn bounding_box(&self) -> Aabb {
+		self.bbox
+	}
+}
@@ -0,0 +1,92 @@
+//! Path tracer following Peter Shirley's "Ray Tracing in One Weekend".
+//!
+//! A scene is a set of [`Hitable`] objects, each one with a [`Material`]
+//! deciding how rays scatter off it. A [`Camera`] traces rays through the
+//! scene and accumulates linear radiance in a [`Framebuffer`]. Gamma encoding,
+//! quantization and the 8-bit and floating-point file writers are separate
+//! stages in [`output`], leaving to the caller what to do with the result.
+//!
+//! ```
+//! use std::sync::Arc;
+//! use ozopathtracer_rs::{output, CameraSettings, Color, HitableBvh, HitableList, Lambertian, LightList, Metal, Point3, Sphere};
+//!
+//! let mut world = HitableList::new(Vec::new());
+//! world.add(Arc::new(Sphere::new(Point3::new(0.0, -100.5, -1.0), 100.0,
+//!     Arc::new(Lambertian::new(Color::new(0.8, 0.8, 0.0))))));
+//! world.add(Arc::new(Sphere::new(Point3::new(0.0, 0.0, -1.0), 0.5,
+//!     Arc::new(Metal::new(Color::new(0.8, 0.6, 0.2), 0.1)))));
+//!
+//! let camera = CameraSettings {
+//!     image_width: 32,
+//!     samples_per_pixel: 4,
+//!     max_depth: 8,
+//!     look_from: Point3::new(0.0, 0.0, 1.0),
+//!     look_at: Point3::new(0.0, 0.0, -1.0),
+//!     ..CameraSettings::default()
+//! }.build();
+//!
+//! // Large scenes are traced through a bounding volume hierarchy
+//! let framebuffer = camera.render(&HitableBvh::from_list(&world), &LightList::default());
+//!
+//! let mut image = framebuffer.resolve();
+//! output::apply_gamma(&mut image, output::DEFAULT_GAMMA);
+//! let image = output::quantize(&image);
+//! assert_eq!(image.dimensions(), (32, 18));
+//! ```
+
+pub mod random;
+pub mod sampler;
+pub mod vec;
+pub mod ray;
+pub mod aabb;
+pub mod hit;
+pub mod bvh;
+pub mod onb;
+pub mod sphere;
+pub mod quad;
+pub mod triangle;
+pub mod mesh;
+pub mod instance;
+pub mod constant_medium;
+pub mod voxel_grid;
+pub mod grid_medium;
+pub mod obj;
+pub mod gltf;
+pub mod ply;
+pub mod stl;
+pub mod light;
+pub mod background;
+pub mod camera;
+pub mod framebuffer;
+pub mod output;
+pub mod material;
+pub mod texture;
+pub mod color;
+pub mod scene;
+
+pub use vec::{Vec3, Point3, Mat4};
+pub use color::Color;
+pub use ray::Ray;
+pub use aabb::Aabb;
+pub use hit::{HitRecord, Hitable, HitableList};
+pub use bvh::HitableBvh;
+pub use sphere::Sphere;
+pub use quad::Quad;
+pub use triangle::Triangle;
+pub use mesh::TriangleMesh;
+pub use instance::Instance;
+pub use constant_medium::ConstantMedium;
+pub use voxel_grid::{VoxelGrid, VoxelType, VoxelGridError, load_raw, load_nrrd};
+pub use grid_medium::GridMedium;
+pub use light::{Light, LightList};
+pub use material::{Material, MaterialRayInteraction, Lambertian, Metal, Conductor, ConductorPreset, Dielectric, DiffuseLight, Principled, Isotropic, HenyeyGreenstein};
+pub use texture::{Texture, SolidColor, CheckerTexture, ImageTexture, ColorSpace, WrapMode, FilterMode, Perlin, NoiseTexture};
+pub use background::Background;
+pub use camera::{Camera, CameraSettings};
+pub use sampler::{Sampler, SamplerKind};
+pub use framebuffer::Framebuffer;
+pub use obj::{ObjModel, ObjGroup, ObjError, load_obj};
+pub use gltf::{GltfModel, GltfCamera, GltfError, load_gltf};
+pub use ply::{PlyError, load_ply};
+pub use stl::{StlError, load_stl};
+pub use scene::{Scene, SceneError, load_scene, parse_scene, random_spheres_scene, studio_scene};
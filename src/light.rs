@@ -0,0 +1,112 @@
+use std::sync::Arc;
+
+use crate::{
+	sampler::Sampler,
+	vec::{Vec3, Point3}
+};
+
+/// Direction towards a point sampled on a light
+pub struct LightSample {
+	pub direction: Vec3,	// Unit direction from the shaded point to the light
+	pub distance: f64,		// Distance to the sampled point along `direction`
+	pub pdf: f64,			// Density of the direction, in solid angle
+}
+
+/// Emissive object that can be sampled directly from the points it lights
+pub trait Light: Send + Sync {
+	/// Samples a direction from `origin` towards the light. None when the light
+	/// cannot be reached from there
+	fn sample(&self, origin: Point3, sampler: &mut dyn Sampler) -> Option<LightSample>;
+
+	/// Solid angle density with which `sample` returns the point `distance` away
+	/// from `origin` along the unit `direction`, zero if that point is not on the light
+	fn pdf(&self, origin: Point3, direction: Vec3, distance: f64) -> f64;
+}
+
+/// Every light of a scene. One of them is picked uniformly for each sample
+#[derive(Clone, Default)]
+pub struct LightList {
+	lights: Vec<Arc<dyn Light>>,
+}
+
+impl LightList {
+	pub fn new(lights: Vec<Arc<dyn Light>>) -> LightList {
+		LightList { lights }
+	}
+
+	pub fn add(&mut self, light: Arc<dyn Light>) {
+		self.lights.push(light)
+	}
+
+	pub fn len(&self) -> usize {
+		self.lights.len()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.lights.is_empty()
+	}
+
+	/// Samples one of the lights. The pdf includes the probability of picking it
+	pub fn sample(&self, origin: Point3, sampler: &mut dyn Sampler) -> Option<LightSample> {
+		if self.lights.is_empty() {
+			return None;
+		}
+
+		let index: usize = ((sampler.get_1d() * self.lights.len() as f64) as usize).min(self.lights.len() - 1);
+		self.lights[index].sample(origin, sampler).map(|sample| LightSample {
+			pdf: sample.pdf / self.lights.len() as f64,
+			..sample
+		})
+	}
+
+	/// Density with which `sample` returns the point hit `distance` away along the
+	/// unit `direction`. Only the light at that point can have been sampled there
+	pub fn pdf(&self, origin: Point3, direction: Vec3, distance: f64) -> f64 {
+		if self.lights.is_empty() {
+			return 0.0;
+		}
+
+		let sum: f64 = self.lights.iter().map(|light| light.pdf(origin, direction, distance)).sum();
+		sum / self.lights.len() as f64
+	}
+}
+
+/// Whether `distance` reaches the same point as `expected` along a ray, up to the
+/// precision of the intersection routines
+pub fn same_distance(distance: f64, expected: f64) -> bool {
+	(distance - expected).abs() <= 1e-4 * expected.max(1.0)
+}
+
+/// Multiple importance sampling weight of a sample taken with density `pdf`,
+/// when `other_pdf` is the density of the other strategy
+pub fn power_heuristic(pdf: f64, other_pdf: f64) -> f64 {
+	let a: f64 = pdf * pdf;
+	let b: f64 = other_pdf * other_pdf;
+	if a + b > 0.0 { a / (a + b) } else { 0.0 }
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{
+		color::WHITE,
+		material::Lambertian,
+		quad::Quad
+	};
+
+	#[test]
+	fn pdf_only_counts_the_light_hit() {
+		// Two unit lights facing the origin, the far one hidden behind the near one
+		let material = Arc::new(Lambertian::new(WHITE));
+		let near: Arc<Quad> = Arc::new(Quad::new(Point3::new(-0.5, -0.5, 1.0), Vec3::new(1.0, 0.0, 0.0), Vec3::new(0.0, 1.0, 0.0), material.clone()));
+		let far: Arc<Quad> = Arc::new(Quad::new(Point3::new(-0.5, -0.5, 2.0), Vec3::new(1.0, 0.0, 0.0), Vec3::new(0.0, 1.0, 0.0), material));
+		let lights: LightList = LightList::new(vec![near.clone(), far.clone()]);
+
+		let origin: Point3 = Point3::zeros();
+		let direction: Vec3 = Vec3::new(0.0, 0.0, 1.0);
+		assert_eq!(lights.pdf(origin, direction, 1.0), near.pdf(origin, direction, 1.0) / 2.0);
+		assert_eq!(lights.pdf(origin, direction, 2.0), far.pdf(origin, direction, 2.0) / 2.0);
+		assert!((near.pdf(origin, direction, 1.0) - 1.0).abs() < 1e-12);
+		assert_eq!(far.pdf(origin, direction, 1.0), 0.0);
+	}
+}
@@ -1,128 +1,133 @@
-mod random;
-mod vec;
-mod ray;
-mod hit;
-mod sphere;
-mod camera;
-mod material;
-mod color;
-
-use std::rc::Rc;
-
-use hit::HitableList;
-use sphere::Sphere;
-use camera::Camera;
-use vec::{Point3, Vec3};
-use color::Color;
-use material::{Lambertian, Metal, Dielectric};
-
-use crate::random::{random_f64, random_f64_range};
-	
-const IMAGE_FILENAME: &str = "test.png";        // Image filename
-
-const ASPECT_RATIO: f64 = 16.0 / 9.0;           // Ratio of image width over height
-const IMAGE_WIDTH: u32 = 1200;                  // Rendered image width in pixel count
-const SAMPLES_PER_PIXEL: u32 = 100;             // Count of random samples for each pixel
-const MAX_DEPTH: u32 = 50;                      // Maximum number of ray bounces into scene
-
-const VFOV: f64 = 20.0;                         // Vertical FOV
-const LOOK_FROM: Point3 = Point3::new(13.0, 2.0, 3.0);
-const LOOK_AT: Point3 = Point3::new(0.0, 0.0, -1.0);
-const VUP: Vec3 = Vec3::new(0.0, 1.0, 0.0);
-
-const DEFOCUS_ANGLE: f64 = 0.6;
-const FOCUS_DIST: f64 = 10.0;
+mod cli;
+
+use std::{io::Write, path::Path, process, time::Instant};
+
+use clap::Parser;
+
+use ozopathtracer_rs::{
+    Background, Camera, CameraSettings, Hitable, HitableBvh, Scene,
+    load_scene, random_spheres_scene, studio_scene,
+    output::{self, OutputFormat, ExrPrecision}
+};
+
+use cli::{Cli, Command, RenderArgs, InfoArgs};
 
 
 fn main() {
-    use std::time::Instant;
-    
-    let camera = Camera::new(IMAGE_WIDTH, ASPECT_RATIO, VFOV, SAMPLES_PER_PIXEL, MAX_DEPTH, LOOK_FROM, LOOK_AT, VUP, DEFOCUS_ANGLE, FOCUS_DIST);
-    
-    let material_ground = Rc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5)));
-    let mut world: HitableList = 
-        HitableList::new(vec![
-            Rc::new(
-                Sphere::new(
-                    Point3::new(0.0, -1000.0, 0.0), 
-                    1000.0, 
-                    material_ground
-                )
-            )
-        ]);
-
-    for a in -11 .. 11 {
-        for b in -11 .. 11 {
-            let choose_material = random_f64();
-            let center: Point3 = Point3::new(a as f64 + 0.9 * random_f64(), 0.2, b as f64 + 0.9 * random_f64());
-
-            if (center - Vec3::new(4.0, 0.2, 0.0)).length() > 0.9 {
-                if choose_material < 0.8 {
-                    world.add(Rc::new(
-                        Sphere::new(
-                            center,
-                            0.2,
-                            Rc::new(
-                                Lambertian::new(Color::random() * Color::random())
-                            )
-                        )
-                    ));
-                }
-                else if choose_material < 0.95 {
-                    world.add(Rc::new(
-                        Sphere::new(
-                            center,
-                            0.2,
-                            Rc::new(
-                                Metal::new(
-                                    Color::random_range(0.5, 1.0),
-                                    random_f64_range(0.0, 0.5)
-                                )
-                            )
-                        )
-                    ));
-               }
-               else {
-                    world.add(Rc::new(
-                        Sphere::new(
-                            center,
-                            0.2,
-                            Rc::new(
-                                Dielectric::new(1.5)
-                            )
-                        )
-                    ));
-                }
-            }
+    let cli: Cli = Cli::parse();
+
+    match cli.command {
+        Command::Render(args) => render(&args),
+        Command::Info(args) => info(&args)
+    }
+}
+
+fn render(args: &RenderArgs) {
+    if let Some(threads) = args.threads {
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(threads as usize)
+            .build_global()
+            .expect("Render thread pool already initialized");
+    }
+
+    let format: OutputFormat = match OutputFormat::from_path(&args.output) {
+        Some(OutputFormat::Exr(_)) if args.half => OutputFormat::Exr(ExrPrecision::Half),
+        Some(format) => format,
+        None => {
+            eprintln!("Unsupported output format for {}, use png, exr, hdr or pfm", args.output.display());
+            process::exit(1);
         }
+    };
+
+    let mut scene: Scene = open_scene(args.scene.as_deref(), args.mesh.as_deref());
+    args.camera.apply(&mut scene.camera);
+    if let Err(err) = scene.camera.validate() {
+        eprintln!("Invalid camera settings: {}", err);
+        process::exit(1);
     }
-    
-    world.add(Rc::new(
-        Sphere::new(
-            Point3::new(0.0, 1.0, 0.0),
-            1.0,
-            Rc::new(Dielectric::new(1.5))
-        )
-    ));
-
-    world.add(Rc::new(
-        Sphere::new(
-            Point3::new(-4.0, 1.0, 0.0),
-            1.0,
-            Rc::new(Lambertian::new(Color::new(0.4, 0.2, 0.1)))
-        )
-    ));
-
-    world.add(Rc::new(
-        Sphere::new(
-            Point3::new(4.0, 1.0, 0.0),
-            1.0,
-            Rc::new(Metal::new(Color::new(0.7, 0.6, 0.5), 0.0))
-        )
-    ));
 
+    let mut camera: Camera = scene.camera.build();
+    camera.set_background(args.background.unwrap_or(scene.background));
+
+    let now = Instant::now();
+    let world: HitableBvh = HitableBvh::from_list(&scene.world);
+    println!("BVH built in {:?}", now.elapsed());
+
+    print!("Rendering");
     let now = Instant::now();
-    camera.render(&world, IMAGE_FILENAME);
+    let framebuffer = camera.render_with_progress(&world, &scene.lights, |_row| {
+        print!(".");
+        std::io::stdout().flush().unwrap_or(());
+    });
     let elapsed = now.elapsed();
-    println!("Elapsed {:?}", elapsed)
+    println!(" Completed");
+    println!("Elapsed {:?}", elapsed);
+
+    if let Err(err) = output::save(&framebuffer, &args.output, format) {
+        eprintln!("Error saving {}: {}", args.output.display(), err);
+        process::exit(1);
+    }
+}
+
+fn info(args: &InfoArgs) {
+    let mut scene: Scene = open_scene(args.scene.as_deref(), args.mesh.as_deref());
+    args.camera.apply(&mut scene.camera);
+    if let Err(err) = scene.camera.validate() {
+        eprintln!("Invalid camera settings: {}", err);
+        process::exit(1);
+    }
+
+    let camera: Camera = scene.camera.build();
+    let settings: &CameraSettings = &scene.camera;
+
+    let now = Instant::now();
+    let world: HitableBvh = HitableBvh::from_list(&scene.world);
+    let bvh_build_time = now.elapsed();
+    let bounds = world.bounding_box();
+
+    match (&args.scene, &args.mesh) {
+        (Some(scene), _) => println!("Scene: {}", scene.display()),
+        (None, Some(mesh)) => println!("Scene: studio with {}", mesh.display()),
+        (None, None) => println!("Scene: random spheres")
+    }
+    println!("Objects: {}", scene.world.objects().len());
+    println!("Lights: {}", scene.lights.len());
+    println!("Bounds: {} - {}", bounds.min, bounds.max);
+    println!("BVH nodes: {} (built in {:?})", world.node_count(), bvh_build_time);
+    println!("Image: {}x{}", camera.image_width(), camera.image_height());
+    println!("Samples per pixel: {}", settings.samples_per_pixel);
+    println!("Max depth: {}", settings.max_depth);
+    println!("Camera: from {} at {} up {}, vfov {}", settings.look_from, settings.look_at, settings.vup, settings.vfov);
+    println!("Defocus: angle {}, focus distance {}", settings.defocus_angle, settings.focus_dist);
+    println!("Background: {}", match scene.background {
+        Background::Solid { color } => format!("solid {}", color),
+        Background::Gradient { bottom, top } => format!("gradient from {} to {}", bottom, top),
+        Background::None => "none".to_string()
+    });
+    println!("Seed: {}", settings.seed);
+    println!("Sampler: {}", settings.sampler);
+    println!("Camera rays: {}", camera.image_width() as u64 * camera.image_height() as u64 * settings.samples_per_pixel as u64);
+}
+
+// Load the given scene file, or the studio scene around the given model, or build
+// the random spheres scene when there is none
+fn open_scene(scene_filename: Option<&Path>, mesh_filename: Option<&Path>) -> Scene {
+    let (filename, loaded) = match (scene_filename, mesh_filename) {
+        (Some(scene_filename), _) => (scene_filename, load_scene(scene_filename)),
+        (None, Some(mesh_filename)) => (mesh_filename, studio_scene(mesh_filename)),
+        (None, None) => return random_spheres_scene()
+    };
+
+    match loaded {
+        Ok(scene) => {
+            for warning in scene.warnings.iter() {
+                eprintln!("Warning: {}", warning);
+            }
+            scene
+        },
+        Err(err) => {
+            eprintln!("Error loading {}: {}", filename.display(), err);
+            process::exit(1);
+        }
+    }
 }
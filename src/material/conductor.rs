@@ -0,0 +1,186 @@
+use crate::{
+	material::{Material, MaterialRayInteraction, microfacet::TrowbridgeReitz},
+	ray::Ray,
+	hit::HitRecord,
+	sampler::Sampler,
+	onb::Onb,
+	vec::{Vec3, reflect, dot},
+	color::{Color, BLACK},
+	texture::{Texture, SolidColor}
+};
+
+use serde::Deserialize;
+use std::sync::Arc;
+
+/// Measured metals, with their complex index of refraction at the red, green and
+/// blue wavelengths
+#[derive(Clone, Copy, PartialEq, Debug, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConductorPreset {
+	Gold,
+	Copper,
+	Aluminum,
+	Silver,
+}
+
+impl ConductorPreset {
+	/// Real part of the index of refraction
+	pub fn eta(self) -> Color {
+		match self {
+			ConductorPreset::Gold => Color::new(0.143, 0.374, 1.442),
+			ConductorPreset::Copper => Color::new(0.200, 0.924, 1.102),
+			ConductorPreset::Aluminum => Color::new(1.657, 0.880, 0.521),
+			ConductorPreset::Silver => Color::new(0.155, 0.117, 0.138)
+		}
+	}
+
+	/// Extinction coefficient, the imaginary part of the index of refraction
+	pub fn k(self) -> Color {
+		match self {
+			ConductorPreset::Gold => Color::new(3.983, 2.385, 1.603),
+			ConductorPreset::Copper => Color::new(3.912, 2.452, 2.142),
+			ConductorPreset::Aluminum => Color::new(9.224, 6.270, 4.837),
+			ConductorPreset::Silver => Color::new(4.828, 3.122, 2.147)
+		}
+	}
+}
+
+enum ConductorFresnel {
+	Complex { eta: Color, k: Color },
+	Reflectance(Arc<dyn Texture>),		// Schlick's approximation from the reflectance at normal incidence
+}
+
+/// Rough metal reflecting off a GGX distribution of microfacets, with Smith masking
+/// and shadowing. Reflections are sampled from the normals visible from the viewer,
+/// so rays are only lost to the light bouncing more than once between microfacets
+pub struct Conductor {
+	fresnel: ConductorFresnel,
+	roughness: Arc<dyn Texture>,	// Perceptual roughness, mean of the channels. The lobe width is its square
+}
+
+impl Conductor {
+	/// Metal of a complex index of refraction `eta + i k`
+	pub fn new(eta: Color, k: Color, roughness: f64) -> Conductor {
+		Conductor { fresnel: ConductorFresnel::Complex { eta, k }, roughness: grey(roughness) }
+	}
+
+	pub fn from_preset(preset: ConductorPreset, roughness: f64) -> Conductor {
+		Conductor::new(preset.eta(), preset.k(), roughness)
+	}
+
+	/// Metal of a given color at normal incidence, brightening towards white at
+	/// grazing angles
+	pub fn from_reflectance(reflectance: Arc<dyn Texture>, roughness: f64) -> Conductor {
+		Conductor { fresnel: ConductorFresnel::Reflectance(reflectance), roughness: grey(roughness) }
+	}
+
+	/// Roughness varying over the surface, read from the mean of the channels
+	pub fn with_roughness(mut self, roughness: Arc<dyn Texture>) -> Conductor {
+		self.roughness = roughness;
+		self
+	}
+
+	/// Roughness whose reflections spread about as much as those of a `Metal` of
+	/// the given fuzz: half the fuzz is close to the lobe width of the same median angle
+	pub fn roughness_from_fuzz(fuzz: f64) -> f64 {
+		(0.5 * fuzz.clamp(0.0, 1.0)).sqrt()
+	}
+
+	fn distribution(&self, hit_record: &HitRecord) -> TrowbridgeReitz {
+		let roughness: Color = self.roughness.filtered(hit_record.u, hit_record.v, hit_record.p, hit_record.uv_footprint);
+		TrowbridgeReitz::from_roughness((roughness.x + roughness.y + roughness.z) / 3.0)
+	}
+
+	// Reflected fraction of the light, for the cosine between the light and the microfacet
+	fn fresnel(&self, hit_record: &HitRecord, cosine: f64) -> Color {
+		let reflectance: Color = match &self.fresnel {
+			ConductorFresnel::Complex { eta, k } => Color::new(
+				fresnel_complex(cosine, eta.x, k.x),
+				fresnel_complex(cosine, eta.y, k.y),
+				fresnel_complex(cosine, eta.z, k.z)
+			),
+			ConductorFresnel::Reflectance(texture) => {
+				let f0: Color = texture.filtered(hit_record.u, hit_record.v, hit_record.p, hit_record.uv_footprint);
+				f0 + (1.0 - cosine.clamp(0.0, 1.0)).powi(5) * (Color::ones() - f0)
+			}
+		};
+		reflectance * hit_record.color
+	}
+}
+
+fn grey(value: f64) -> Arc<dyn Texture> {
+	Arc::new(SolidColor::new(Color::new(value, value, value)))
+}
+
+// Fresnel reflectance of unpolarized light at the boundary with a conductor of
+// complex index of refraction `eta + i k`
+fn fresnel_complex(cos_theta: f64, eta: f64, k: f64) -> f64 {
+	let cos2: f64 = cos_theta.clamp(0.0, 1.0).powi(2);
+	let sin2: f64 = 1.0 - cos2;
+	let eta2: f64 = eta * eta;
+	let k2: f64 = k * k;
+
+	let t0: f64 = eta2 - k2 - sin2;
+	let a2_plus_b2: f64 = (t0 * t0 + 4.0 * eta2 * k2).sqrt();
+	let t1: f64 = a2_plus_b2 + cos2;
+	let a: f64 = (0.5 * (a2_plus_b2 + t0)).max(0.0).sqrt();
+	let t2: f64 = 2.0 * cos2.sqrt() * a;
+	let rs: f64 = (t1 - t2) / (t1 + t2);
+
+	let t3: f64 = cos2 * a2_plus_b2 + sin2 * sin2;
+	let t4: f64 = t2 * sin2;
+	let rp: f64 = rs * (t3 - t4) / (t3 + t4);
+	0.5 * (rp + rs)
+}
+
+impl Material for Conductor {
+	fn scatter(&self, ray_in: &Ray, hit_record: &HitRecord, sampler: &mut dyn Sampler) -> Option<MaterialRayInteraction> {
+		let onb: Onb = Onb::new(hit_record.normal);
+		let wo: Vec3 = onb.to_local(-ray_in.dir().unit_vector());
+		let distribution: TrowbridgeReitz = self.distribution(hit_record);
+		if distribution.is_smooth() {
+			let reflected: Vec3 = reflect(ray_in.dir().unit_vector(), hit_record.normal);
+			return Some(MaterialRayInteraction::specular(self.fresnel(hit_record, wo.z), Ray::new(hit_record.p, reflected)));
+		}
+		if wo.z <= 0.0 {
+			return None;
+		}
+
+		// Microfacets shadowing the reflection absorb it, as the light bouncing
+		// between them is not followed
+		let h: Vec3 = distribution.sample_visible_normal(wo, sampler.get_2d());
+		let wi: Vec3 = reflect(-wo, h);
+		if wi.z <= 0.0 {
+			return None;
+		}
+
+		let pdf: f64 = distribution.visible_normal_pdf(wo, h) / (4.0 * dot(wo, h));
+		let attenuation: Color = (distribution.g(wo, wi) / distribution.g1(wo)) * self.fresnel(hit_record, dot(wi, h));
+		Some(MaterialRayInteraction::new(attenuation, Ray::new(hit_record.p, onb.local(wi)), pdf))
+	}
+
+	fn eval(&self, hit_record: &HitRecord, wi: Vec3, wo: Vec3) -> Color {
+		let distribution: TrowbridgeReitz = self.distribution(hit_record);
+		let onb: Onb = Onb::new(hit_record.normal);
+		let (wi, wo) = (onb.to_local(wi), onb.to_local(wo));
+		if distribution.is_smooth() || wi.z <= 0.0 || wo.z <= 0.0 {
+			return BLACK;
+		}
+
+		let h: Vec3 = (wi + wo).unit_vector();
+		let weight: f64 = distribution.d(h) * distribution.g(wo, wi) / (4.0 * wo.z * wi.z);
+		weight * self.fresnel(hit_record, dot(wi, h))
+	}
+
+	fn pdf(&self, hit_record: &HitRecord, wi: Vec3, wo: Vec3) -> f64 {
+		let distribution: TrowbridgeReitz = self.distribution(hit_record);
+		let onb: Onb = Onb::new(hit_record.normal);
+		let (wi, wo) = (onb.to_local(wi), onb.to_local(wo));
+		if distribution.is_smooth() || wi.z <= 0.0 || wo.z <= 0.0 {
+			return 0.0;
+		}
+
+		let h: Vec3 = (wi + wo).unit_vector();
+		distribution.visible_normal_pdf(wo, h) / (4.0 * dot(wo, h))
+	}
+}
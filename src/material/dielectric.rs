@@ -1,19 +1,65 @@
 use crate::{
-	material::{Material, MaterialRayInteraction},
+	material::{Material, MaterialRayInteraction, microfacet::TrowbridgeReitz},
 	ray::Ray,
 	hit::HitRecord,
-	vec::{Vec3, refract, reflect}, 
-	random::random_f64,
-	color::WHITE
+	onb::Onb,
+	vec::{Vec3, refract, reflect},
+	sampler::Sampler,
+	color::{Color, BLACK, WHITE},
+	texture::{Texture, SolidColor}
 };
 
+use std::sync::Arc;
+
+/// Glass-like interface reflecting and refracting light. Smooth by default, rough
+/// surfaces spread both the reflections and the refractions over a GGX distribution
+/// of microfacets, like frosted glass
 pub struct Dielectric {
-	ir : f64,   // Index of refraction
+	ir: Arc<dyn Texture>,			// Index of refraction, mean of the channels
+	roughness: Arc<dyn Texture>,	// Perceptual roughness, mean of the channels
+	absorption: Color,				// Absorption coefficient inside, per unit of distance
 }
 
 impl Dielectric {
 	pub fn new(index_of_refraction: f64) -> Dielectric {
-		Dielectric { ir: index_of_refraction }
+		let ir: Color = Color::new(index_of_refraction, index_of_refraction, index_of_refraction);
+		Dielectric::from_texture(Arc::new(SolidColor::new(ir)))
+	}
+
+	/// Dielectric whose index of refraction varies over the surface, read from the
+	/// mean of the channels
+	pub fn from_texture(index_of_refraction: Arc<dyn Texture>) -> Dielectric {
+		Dielectric { ir: index_of_refraction, roughness: Arc::new(SolidColor::new(BLACK)), absorption: BLACK }
+	}
+
+	/// Roughness of the interface, read from the mean of the channels. 0 is smooth
+	pub fn with_roughness(mut self, roughness: Arc<dyn Texture>) -> Dielectric {
+		self.roughness = roughness;
+		self
+	}
+
+	/// Light crossing a distance `d` inside keeps `exp(-absorption * d)` of each
+	/// channel, following the Beer-Lambert law. Thick parts of colored glass get darker
+	pub fn with_absorption(mut self, absorption: Color) -> Dielectric {
+		self.absorption = absorption;
+		self
+	}
+
+	fn index_of_refraction(&self, hit_record: &HitRecord) -> f64 {
+		let ir: Color = self.ir.filtered(hit_record.u, hit_record.v, hit_record.p, hit_record.uv_footprint);
+		(ir.x + ir.y + ir.z) / 3.0
+	}
+
+	// Index of refraction across the surface over the one on the side of the normal,
+	// which faces the incoming ray
+	fn relative_ior(&self, hit_record: &HitRecord) -> f64 {
+		let ir: f64 = self.index_of_refraction(hit_record);
+		if hit_record.front_face { ir } else { 1.0 / ir }
+	}
+
+	fn distribution(&self, hit_record: &HitRecord) -> TrowbridgeReitz {
+		let roughness: Color = self.roughness.filtered(hit_record.u, hit_record.v, hit_record.p, hit_record.uv_footprint);
+		TrowbridgeReitz::from_roughness((roughness.x + roughness.y + roughness.z) / 3.0)
 	}
 
 	fn reflectance(&self, cosine: f64, ref_idx: f64) -> f64 {
@@ -22,28 +68,89 @@ impl Dielectric {
 		r0 *= r0;
 		r0 + (1.0 - r0) * (1.0 - cosine).powi(5)
 	}
-}
 
-impl Material for Dielectric {
-	fn scatter(&self, ray_in: &Ray, hit_record: &HitRecord) -> Option<MaterialRayInteraction> {
-		let refraction_ratio : f64 = if hit_record.front_face { 1.0 / self.ir } else { self.ir };
+	fn scatter_smooth(&self, ray_in: &Ray, hit_record: &HitRecord, sampler: &mut dyn Sampler) -> Option<MaterialRayInteraction> {
+		let ir: f64 = self.index_of_refraction(hit_record);
+		let refraction_ratio : f64 = if hit_record.front_face { 1.0 / ir } else { ir };
 		let unit_direction: Vec3 = ray_in.dir().unit_vector();
 		let cos_theta: f64 = -unit_direction.dot(hit_record.normal).min(1.0);
 		let sin_theta: f64 = (1.0  - cos_theta.powi(2)).sqrt();
 
 		let cannot_refract: bool = (refraction_ratio * sin_theta) > 1.0;
 
-		let direction: Vec3 = 
-			if cannot_refract || self.reflectance(cos_theta, refraction_ratio) > random_f64()
+		let (direction, attenuation): (Vec3, Color) =
+			if cannot_refract || self.reflectance(cos_theta, refraction_ratio) > sampler.get_1d()
 			{
-				// Must Reflect 
-				reflect(unit_direction, hit_record.normal) 
-			} else { 
-				// Can Refract
-				refract(unit_direction, hit_record.normal, refraction_ratio)
+				// Must Reflect
+				(reflect(unit_direction, hit_record.normal), WHITE)
+			} else {
+				// Can Refract, the radiance scaled by the change of solid angle
+				(refract(unit_direction, hit_record.normal, refraction_ratio), refraction_ratio * refraction_ratio * WHITE)
 			};
 
-		Some(MaterialRayInteraction::new(WHITE, Ray::new(hit_record.p, direction)))
+		Some(MaterialRayInteraction::specular(attenuation, Ray::new(hit_record.p, direction)))
+	}
+}
+
+impl Material for Dielectric {
+	fn scatter(&self, ray_in: &Ray, hit_record: &HitRecord, sampler: &mut dyn Sampler) -> Option<MaterialRayInteraction> {
+		let distribution: TrowbridgeReitz = self.distribution(hit_record);
+		if distribution.is_smooth() {
+			return self.scatter_smooth(ray_in, hit_record, sampler);
+		}
+
+		let onb: Onb = Onb::new(hit_record.normal);
+		let wo: Vec3 = onb.to_local(-ray_in.dir().unit_vector());
+		if wo.z <= 0.0 {
+			return None;
+		}
+
+		// Reflect or refract through a visible microfacet, in proportion to its Fresnel reflectance
+		let eta: f64 = self.relative_ior(hit_record);
+		let wi: Vec3 = distribution.sample_dielectric(wo, eta, sampler.get_2d(), sampler.get_1d())?;
+		let pdf: f64 = distribution.dielectric_pdf(wo, wi, eta);
+
+		// Both lobes are sampled in proportion to their share of the BSDF, which leaves
+		// the masking and shadowing as weight, and the 1 / eta² of refractions
+		let mut attenuation: f64 = distribution.g(wo, wi) / distribution.g1(wo);
+		if wi.z < 0.0 {
+			attenuation /= eta * eta;
+		}
+		Some(MaterialRayInteraction::new(
+			Color::new(attenuation, attenuation, attenuation),
+			Ray::new(hit_record.p, onb.local(wi)),
+			pdf
+		))
 	}
 
+	fn eval(&self, hit_record: &HitRecord, wi: Vec3, wo: Vec3) -> Color {
+		let distribution: TrowbridgeReitz = self.distribution(hit_record);
+		let onb: Onb = Onb::new(hit_record.normal);
+		let (wi, wo) = (onb.to_local(wi), onb.to_local(wo));
+		if distribution.is_smooth() || wo.z <= 0.0 || wi.z == 0.0 {
+			return BLACK;
+		}
+
+		let bsdf: f64 = distribution.dielectric_bsdf(wo, wi, self.relative_ior(hit_record));
+		Color::new(bsdf, bsdf, bsdf)
+	}
+
+	fn pdf(&self, hit_record: &HitRecord, wi: Vec3, wo: Vec3) -> f64 {
+		let distribution: TrowbridgeReitz = self.distribution(hit_record);
+		let onb: Onb = Onb::new(hit_record.normal);
+		let (wi, wo) = (onb.to_local(wi), onb.to_local(wo));
+		if distribution.is_smooth() || wo.z <= 0.0 || wi.z == 0.0 {
+			return 0.0;
+		}
+
+		distribution.dielectric_pdf(wo, wi, self.relative_ior(hit_record))
+	}
+
+	fn transmittance(&self, _hit_record: &HitRecord, distance: f64) -> Color {
+		Color::new(
+			(-self.absorption.x * distance).exp(),
+			(-self.absorption.y * distance).exp(),
+			(-self.absorption.z * distance).exp()
+		)
+	}
 }
@@ -0,0 +1,34 @@
+use crate::{
+	material::{Material, MaterialRayInteraction},
+	ray::Ray,
+	hit::HitRecord,
+	sampler::Sampler,
+	color::Color,
+	texture::{Texture, SolidColor}
+};
+
+use std::sync::Arc;
+
+pub struct DiffuseLight {
+	emit: Arc<dyn Texture>,
+}
+
+impl DiffuseLight {
+	pub fn new(emit: Color) -> DiffuseLight {
+		DiffuseLight::from_texture(Arc::new(SolidColor::new(emit)))
+	}
+
+	pub fn from_texture(emit: Arc<dyn Texture>) -> DiffuseLight {
+		DiffuseLight { emit }
+	}
+}
+
+impl Material for DiffuseLight {
+	fn scatter(&self, _ray_in: &Ray, _hit_record: &HitRecord, _sampler: &mut dyn Sampler) -> Option<MaterialRayInteraction> {
+		None
+	}
+
+	fn emitted(&self, _ray_in: &Ray, hit_record: &HitRecord) -> Color {
+		self.emit.filtered(hit_record.u, hit_record.v, hit_record.p, hit_record.uv_footprint)
+	}
+}
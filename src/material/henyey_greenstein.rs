@@ -0,0 +1,84 @@
+use crate::{
+	material::{Material, MaterialRayInteraction},
+	ray::Ray,
+	hit::HitRecord,
+	onb::Onb,
+	vec::{Vec3, dot},
+	color::Color,
+	sampler::Sampler,
+	texture::{Texture, SolidColor}
+};
+
+use std::{f64::consts::PI, sync::Arc};
+
+/// Phase function of a medium scattering light mostly forward, or backward, around
+/// the direction it travels. The anisotropy `g` is the mean cosine of the scattering
+/// angle: positive values scatter forward, as haze does, negative ones backward and
+/// 0 is isotropic
+pub struct HenyeyGreenstein {
+	albedo: Arc<dyn Texture>,
+	g: f64,
+}
+
+impl HenyeyGreenstein {
+	pub fn new(color: Color, g: f64) -> HenyeyGreenstein {
+		HenyeyGreenstein::from_texture(Arc::new(SolidColor::new(color)), g)
+	}
+
+	pub fn from_texture(albedo: Arc<dyn Texture>, g: f64) -> HenyeyGreenstein {
+		HenyeyGreenstein { albedo, g: g.clamp(-0.99, 0.99) }
+	}
+
+	fn albedo(&self, hit_record: &HitRecord) -> Color {
+		self.albedo.filtered(hit_record.u, hit_record.v, hit_record.p, hit_record.uv_footprint) * hit_record.color
+	}
+
+	// Density of the cosine between the directions the light travels before and after
+	// scattering, over the sphere of directions
+	fn phase(&self, cos_theta: f64) -> f64 {
+		let g2: f64 = self.g * self.g;
+		let denominator: f64 = 1.0 + g2 - 2.0 * self.g * cos_theta;
+		(1.0 - g2) / (4.0 * PI * denominator * denominator.sqrt())
+	}
+}
+
+impl Material for HenyeyGreenstein {
+	fn scatter(&self, ray_in: &Ray, hit_record: &HitRecord, sampler: &mut dyn Sampler) -> Option<MaterialRayInteraction> {
+		// Inverted cumulative distribution of the cosine with the incoming direction
+		let (u1, u2) = sampler.get_2d();
+		let cos_theta: f64 = if self.g.abs() < 1e-3 {
+			1.0 - 2.0 * u1
+		} else {
+			let g2: f64 = self.g * self.g;
+			let s: f64 = (1.0 - g2) / (1.0 - self.g + 2.0 * self.g * u1);
+			((1.0 + g2 - s * s) / (2.0 * self.g)).clamp(-1.0, 1.0)
+		};
+		let sin_theta: f64 = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+		let phi: f64 = 2.0 * PI * u2;
+
+		let forward: Vec3 = ray_in.dir().unit_vector();
+		let direction: Vec3 = Onb::new(forward).local(Vec3::new(sin_theta * phi.cos(), sin_theta * phi.sin(), cos_theta));
+		Some(MaterialRayInteraction::new(
+			self.albedo(hit_record),
+			Ray::new(hit_record.p, direction),
+			self.phase(cos_theta)
+		))
+	}
+
+	// The light arriving along `wi` travels towards `-wi`, and leaves towards `wo`
+	fn eval(&self, hit_record: &HitRecord, wi: Vec3, wo: Vec3) -> Color {
+		self.phase(-dot(wi, wo)) * self.albedo(hit_record)
+	}
+
+	fn pdf(&self, _hit_record: &HitRecord, wi: Vec3, wo: Vec3) -> f64 {
+		self.phase(-dot(wi, wo))
+	}
+
+	fn is_volumetric(&self) -> bool {
+		true
+	}
+
+	fn scattering_albedo(&self, hit_record: &HitRecord) -> Color {
+		self.albedo(hit_record)
+	}
+}
@@ -0,0 +1,57 @@
+use crate::{
+	material::{Material, MaterialRayInteraction},
+	ray::Ray,
+	hit::HitRecord,
+	vec::Vec3,
+	color::Color,
+	sampler::Sampler,
+	texture::{Texture, SolidColor}
+};
+
+use std::{f64::consts::PI, sync::Arc};
+
+/// Phase function of a medium scattering light equally in every direction
+pub struct Isotropic {
+	albedo: Arc<dyn Texture>,
+}
+
+impl Isotropic {
+	pub fn new(color: Color) -> Isotropic {
+		Isotropic::from_texture(Arc::new(SolidColor::new(color)))
+	}
+
+	pub fn from_texture(albedo: Arc<dyn Texture>) -> Isotropic {
+		Isotropic { albedo }
+	}
+
+	fn albedo(&self, hit_record: &HitRecord) -> Color {
+		self.albedo.filtered(hit_record.u, hit_record.v, hit_record.p, hit_record.uv_footprint) * hit_record.color
+	}
+}
+
+impl Material for Isotropic {
+	fn scatter(&self, _ray_in: &Ray, hit_record: &HitRecord, sampler: &mut dyn Sampler) -> Option<MaterialRayInteraction> {
+		// Uniform sphere sampling, the phase function and pdf cancel out
+		Some(MaterialRayInteraction::new(
+			self.albedo(hit_record),
+			Ray::new(hit_record.p, Vec3::random_unit_vector(sampler)),
+			1.0 / (4.0 * PI)
+		))
+	}
+
+	fn eval(&self, hit_record: &HitRecord, _wi: Vec3, _wo: Vec3) -> Color {
+		self.albedo(hit_record) / (4.0 * PI)
+	}
+
+	fn pdf(&self, _hit_record: &HitRecord, _wi: Vec3, _wo: Vec3) -> f64 {
+		1.0 / (4.0 * PI)
+	}
+
+	fn is_volumetric(&self) -> bool {
+		true
+	}
+
+	fn scattering_albedo(&self, hit_record: &HitRecord) -> Color {
+		self.albedo(hit_record)
+	}
+}
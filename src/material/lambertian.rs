@@ -2,31 +2,51 @@ use crate::{
 	material::{Material, MaterialRayInteraction},
 	ray::Ray,
 	hit::HitRecord,
-	vec::Vec3,
-	color::Color
+	vec::{Vec3, dot},
+	color::{Color, BLACK},
+	onb::Onb,
+	sampler::Sampler,
+	texture::{Texture, SolidColor}
 };
 
+use std::{f64::consts::PI, sync::Arc};
+
 pub struct Lambertian {
-	albedo: Color,
+	albedo: Arc<dyn Texture>,
 }
 
 impl Lambertian {
 	pub fn new(color: Color) -> Lambertian {
-		Lambertian { albedo: color }
+		Lambertian::from_texture(Arc::new(SolidColor::new(color)))
+	}
+
+	pub fn from_texture(albedo: Arc<dyn Texture>) -> Lambertian {
+		Lambertian { albedo }
+	}
+
+	fn albedo(&self, hit_record: &HitRecord) -> Color {
+		self.albedo.filtered(hit_record.u, hit_record.v, hit_record.p, hit_record.uv_footprint) * hit_record.color
 	}
 }
 
 impl Material for Lambertian {
-	fn scatter(&self, _ray_in: &Ray, hit_record: &HitRecord) -> Option<MaterialRayInteraction> {
-		let mut scatter_direction = hit_record.normal + Vec3::random_unit_vector();
-
-		if scatter_direction.near_zero() {
-			scatter_direction = hit_record.normal;
-		}		
+	fn scatter(&self, _ray_in: &Ray, hit_record: &HitRecord, sampler: &mut dyn Sampler) -> Option<MaterialRayInteraction> {
+		// Cosine-weighted hemisphere sampling, the cosine and pdf cancel out
+		let scatter_direction: Vec3 = Onb::new(hit_record.normal).local(Vec3::random_cosine_direction(sampler));
+		let pdf: f64 = dot(scatter_direction, hit_record.normal).max(0.0) / PI;
 
 		Some(MaterialRayInteraction::new(
-			self.albedo,
-			Ray::new(hit_record.p, scatter_direction)
+			self.albedo(hit_record),
+			Ray::new(hit_record.p, scatter_direction),
+			pdf
 		))
 	}
-}
\ No newline at end of file
+
+	fn eval(&self, hit_record: &HitRecord, wi: Vec3, _wo: Vec3) -> Color {
+		if dot(wi, hit_record.normal) > 0.0 { self.albedo(hit_record) / PI } else { BLACK }
+	}
+
+	fn pdf(&self, hit_record: &HitRecord, wi: Vec3, _wo: Vec3) -> f64 {
+		dot(wi, hit_record.normal).max(0.0) / PI
+	}
+}
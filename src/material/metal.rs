@@ -2,32 +2,96 @@ use crate::{
 	material::{Material, MaterialRayInteraction},
 	ray::Ray,
 	hit::HitRecord,
-	vec::{Vec3, reflect},
-	color::Color
+	sampler::Sampler,
+	vec::{Vec3, reflect, dot},
+	color::{Color, BLACK},
+	texture::{Texture, SolidColor}
 };
 
+use std::{f64::consts::PI, sync::Arc};
+
 pub struct Metal {
-	albedo: Color,
-	fuzz: f64,
+	albedo: Arc<dyn Texture>,
+	fuzz: Arc<dyn Texture>,		// Mean of the channels, at most 1
 }
 
 impl Metal {
 	pub fn new(color: Color, fuzz: f64) -> Metal {
-		Metal { 
-			albedo: color,
-			fuzz: if fuzz < 1.0 { fuzz } else { 1.0 }
+		Metal::from_texture(Arc::new(SolidColor::new(color)), fuzz)
+	}
+
+	pub fn from_texture(albedo: Arc<dyn Texture>, fuzz: f64) -> Metal {
+		Metal::from_textures(albedo, Arc::new(SolidColor::new(Color::new(fuzz, fuzz, fuzz))))
+	}
+
+	/// Metal whose fuzz also varies over the surface, read from the mean of the channels
+	pub fn from_textures(albedo: Arc<dyn Texture>, fuzz: Arc<dyn Texture>) -> Metal {
+		Metal { albedo, fuzz }
+	}
+
+	fn fuzz(&self, hit_record: &HitRecord) -> f64 {
+		let fuzz: Color = self.fuzz.filtered(hit_record.u, hit_record.v, hit_record.p, hit_record.uv_footprint);
+		((fuzz.x + fuzz.y + fuzz.z) / 3.0).min(1.0)
+	}
+
+	fn albedo(&self, hit_record: &HitRecord) -> Color {
+		self.albedo.filtered(hit_record.u, hit_record.v, hit_record.p, hit_record.uv_footprint) * hit_record.color
+	}
+
+	// Density of the unit direction `d` when sampling `reflected + fuzz * u`, with `u` 
+	// uniform on the unit sphere. Every direction crossing the fuzz sphere gets the 
+	// projected area of the points it crosses
+	fn fuzz_pdf(reflected: Vec3, d: Vec3, fuzz: f64) -> f64 {
+		let b: f64 = dot(d, reflected);
+		let discriminant: f64 = b * b - (1.0 - fuzz * fuzz);
+		if discriminant < 0.0 {
+			return 0.0;
 		}
+
+		let sqrtd: f64 = discriminant.sqrt();
+		[b - sqrtd, b + sqrtd].iter()
+			.filter(|t| **t > 0.0)
+			.map(|t| {
+				let sphere_normal: Vec3 = (*t * d - reflected) / fuzz;
+				let cosine: f64 = dot(sphere_normal, d).abs().max(1e-8);
+				t * t / (cosine * 4.0 * PI * fuzz * fuzz)
+			})
+			.sum()
 	}
 }
 
 impl Material for Metal {
-	fn scatter(&self, ray_in: &Ray, hit_record: &HitRecord) -> Option<MaterialRayInteraction> {
-		let reflected : Vec3 = reflect(ray_in.dir(), hit_record.normal);
-		let scattered : Ray = Ray::new(hit_record.p, reflected + self.fuzz * Vec3::random_unit_vector());
+	fn scatter(&self, ray_in: &Ray, hit_record: &HitRecord, sampler: &mut dyn Sampler) -> Option<MaterialRayInteraction> {
+		let reflected : Vec3 = reflect(ray_in.dir().unit_vector(), hit_record.normal);
+		let fuzz: f64 = self.fuzz(hit_record);
+		if fuzz <= 0.0 {
+			return Some(MaterialRayInteraction::specular(self.albedo(hit_record), Ray::new(hit_record.p, reflected)));
+		}
+
+		// Fuzzy reflections are absorbed when they end up below the surface
+		let scattered : Ray = Ray::new(hit_record.p, reflected + fuzz * Vec3::random_unit_vector(sampler));
 		if Vec3::dot(scattered.dir(), hit_record.normal) > 0.0 {
-			Some(MaterialRayInteraction::new(self.albedo, scattered))
+			let pdf: f64 = Metal::fuzz_pdf(reflected, scattered.dir().unit_vector(), fuzz);
+			Some(MaterialRayInteraction::new(self.albedo(hit_record), scattered, pdf))
 		} else {
 			None
 		}
 	}
-}
\ No newline at end of file
+
+	fn eval(&self, hit_record: &HitRecord, wi: Vec3, wo: Vec3) -> Color {
+		// BSDF matching the sampling, so every scattered ray is weighted by the albedo
+		let cosine: f64 = dot(wi, hit_record.normal);
+		if cosine <= 0.0 {
+			return BLACK;
+		}
+		(self.pdf(hit_record, wi, wo) / cosine) * self.albedo(hit_record)
+	}
+
+	fn pdf(&self, hit_record: &HitRecord, wi: Vec3, wo: Vec3) -> f64 {
+		let fuzz: f64 = self.fuzz(hit_record);
+		if fuzz <= 0.0 {
+			return 0.0;
+		}
+		Metal::fuzz_pdf(reflect(-wo, hit_record.normal), wi, fuzz)
+	}
+}
@@ -0,0 +1,178 @@
+use crate::vec::{Vec3, cross, dot, reflect};
+
+use std::f64::consts::PI;
+
+// Below this width a lobe is drawn as a perfect mirror or refraction
+const MIN_ALPHA: f64 = 1e-3;
+
+// GGX (Trowbridge-Reitz) distribution of microfacet normals with Smith masking and
+// shadowing. Directions are given in the local frame of the surface, normal along Z
+#[derive(Clone, Copy)]
+pub(crate) struct TrowbridgeReitz {
+	alpha: f64,			// Width of the lobe, the square of the perceptual roughness
+}
+
+impl TrowbridgeReitz {
+	pub(crate) fn from_roughness(roughness: f64) -> TrowbridgeReitz {
+		let roughness: f64 = roughness.clamp(0.0, 1.0);
+		TrowbridgeReitz { alpha: roughness * roughness }
+	}
+
+	pub(crate) fn is_smooth(&self) -> bool {
+		self.alpha < MIN_ALPHA
+	}
+
+	// Density of the microfacet normals, projected on the macro surface
+	pub(crate) fn d(&self, h: Vec3) -> f64 {
+		if h.z <= 0.0 {
+			return 0.0;
+		}
+		let alpha2: f64 = self.alpha * self.alpha;
+		let denominator: f64 = h.z * h.z * (alpha2 - 1.0) + 1.0;
+		alpha2 / (PI * denominator * denominator)
+	}
+
+	// Smith auxiliary function of the microfacets hidden seen from `w`
+	fn lambda(&self, w: Vec3) -> f64 {
+		let cos2: f64 = w.z * w.z;
+		if cos2 <= 0.0 {
+			return f64::INFINITY;
+		}
+		let tan2: f64 = (1.0 - cos2).max(0.0) / cos2;
+		0.5 * (-1.0 + (1.0 + self.alpha * self.alpha * tan2).sqrt())
+	}
+
+	// Fraction of the microfacets visible from `w`
+	pub(crate) fn g1(&self, w: Vec3) -> f64 {
+		1.0 / (1.0 + self.lambda(w))
+	}
+
+	// Fraction of the microfacets visible from both directions, height correlated
+	pub(crate) fn g(&self, wo: Vec3, wi: Vec3) -> f64 {
+		1.0 / (1.0 + self.lambda(wo) + self.lambda(wi))
+	}
+
+	// Density of the normals visible from `wo`, with which `sample_visible_normal`
+	// draws `h`
+	pub(crate) fn visible_normal_pdf(&self, wo: Vec3, h: Vec3) -> f64 {
+		let cos_o: f64 = wo.z.abs();
+		if cos_o <= 0.0 {
+			return 0.0;
+		}
+		self.g1(wo) * dot(wo, h).abs() * self.d(h) / cos_o
+	}
+
+	// Normal visible from `wo`, from Heitz, "Sampling the GGX Distribution of Visible
+	// Normals" (2018). The lobe is stretched to a hemisphere, sampled, then unstretched
+	pub(crate) fn sample_visible_normal(&self, wo: Vec3, u: (f64, f64)) -> Vec3 {
+		let wo: Vec3 = if wo.z < 0.0 { -wo } else { wo };
+		let vh: Vec3 = Vec3::new(self.alpha * wo.x, self.alpha * wo.y, wo.z).unit_vector();
+
+		let length2: f64 = vh.x * vh.x + vh.y * vh.y;
+		let t1: Vec3 = if length2 > 0.0 {
+			Vec3::new(-vh.y, vh.x, 0.0) / length2.sqrt()
+		} else {
+			Vec3::new(1.0, 0.0, 0.0)
+		};
+		let t2: Vec3 = cross(vh, t1);
+
+		// Disk point, squeezed towards the part of the hemisphere facing `wo`
+		let r: f64 = u.0.sqrt();
+		let phi: f64 = 2.0 * PI * u.1;
+		let p1: f64 = r * phi.cos();
+		let s: f64 = 0.5 * (1.0 + vh.z);
+		let p2: f64 = (1.0 - s) * (1.0 - p1 * p1).max(0.0).sqrt() + s * r * phi.sin();
+
+		let nh: Vec3 = p1 * t1 + p2 * t2 + (1.0 - p1 * p1 - p2 * p2).max(0.0).sqrt() * vh;
+		Vec3::new(self.alpha * nh.x, self.alpha * nh.y, nh.z.max(1e-6)).unit_vector()
+	}
+
+	// BSDF of a rough dielectric interface, reflecting and refracting into a medium
+	// of relative index of refraction `eta` below the surface. Refraction squeezes
+	// or spreads the solid angle of the light, which scales its radiance by 1 / eta²
+	pub(crate) fn dielectric_bsdf(&self, wo: Vec3, wi: Vec3, eta: f64) -> f64 {
+		let h: Vec3 = match half_vector(wi, wo, eta) {
+			Some(h) => h,
+			None => return 0.0
+		};
+		let reflectance: f64 = fresnel_dielectric(dot(wo, h), eta);
+		if wi.z > 0.0 {
+			self.d(h) * self.g(wo, wi) * reflectance / (4.0 * wo.z * wi.z)
+		} else {
+			let denominator: f64 = (dot(wi, h) + dot(wo, h) / eta).powi(2);
+			(1.0 - reflectance) * self.d(h) * self.g(wo, wi) * (dot(wi, h) * dot(wo, h) / (wi.z * wo.z * denominator)).abs() / (eta * eta)
+		}
+	}
+
+	// Density with which `sample_dielectric` draws `wi`
+	pub(crate) fn dielectric_pdf(&self, wo: Vec3, wi: Vec3, eta: f64) -> f64 {
+		let h: Vec3 = match half_vector(wi, wo, eta) {
+			Some(h) => h,
+			None => return 0.0
+		};
+		let reflectance: f64 = fresnel_dielectric(dot(wo, h), eta);
+		let normal_pdf: f64 = self.visible_normal_pdf(wo, h);
+		if wi.z > 0.0 {
+			reflectance * normal_pdf / (4.0 * dot(wo, h))
+		} else {
+			let denominator: f64 = (dot(wi, h) + dot(wo, h) / eta).powi(2);
+			(1.0 - reflectance) * normal_pdf * dot(wi, h).abs() / denominator
+		}
+	}
+
+	// Direction reflected or refracted through a microfacet visible from `wo`, in
+	// proportion to its Fresnel reflectance. None when it ends up on the wrong side
+	pub(crate) fn sample_dielectric(&self, wo: Vec3, eta: f64, u: (f64, f64), u_lobe: f64) -> Option<Vec3> {
+		let h: Vec3 = self.sample_visible_normal(wo, u);
+		if u_lobe < fresnel_dielectric(dot(wo, h), eta) {
+			let wi: Vec3 = reflect(-wo, h);
+			if wi.z > 0.0 { Some(wi) } else { None }
+		} else {
+			let wi: Vec3 = refract_through(wo, h, eta)?;
+			if wi.z < 0.0 { Some(wi) } else { None }
+		}
+	}
+}
+
+// Microfacet normal of a pair of directions, None for pairs no microfacet facing
+// both of them connects. Refractions bend by the relative index `eta`
+fn half_vector(wi: Vec3, wo: Vec3, eta: f64) -> Option<Vec3> {
+	let is_reflection: bool = wi.z * wo.z > 0.0;
+	let h: Vec3 = if is_reflection { wi + wo } else { eta * wi + wo };
+	if h.near_zero() {
+		return None;
+	}
+
+	let h: Vec3 = if h.z < 0.0 { -h.unit_vector() } else { h.unit_vector() };
+	if dot(h, wi) * wi.z <= 0.0 || dot(h, wo) * wo.z <= 0.0 {
+		return None;
+	}
+	Some(h)
+}
+
+// Fresnel reflectance of unpolarized light crossing into a dielectric of relative
+// index of refraction `eta`, 1 under total internal reflection
+fn fresnel_dielectric(cos_theta: f64, eta: f64) -> f64 {
+	let cos_i: f64 = cos_theta.clamp(0.0, 1.0);
+	let sin2_t: f64 = (1.0 - cos_i * cos_i) / (eta * eta);
+	if sin2_t >= 1.0 {
+		return 1.0;
+	}
+
+	let cos_t: f64 = (1.0 - sin2_t).sqrt();
+	let r_parallel: f64 = (eta * cos_i - cos_t) / (eta * cos_i + cos_t);
+	let r_perpendicular: f64 = (cos_i - eta * cos_t) / (cos_i + eta * cos_t);
+	0.5 * (r_parallel * r_parallel + r_perpendicular * r_perpendicular)
+}
+
+// Direction refracted from `wo` through a microfacet of normal `h` on its side,
+// None under total internal reflection
+fn refract_through(wo: Vec3, h: Vec3, eta: f64) -> Option<Vec3> {
+	let cos_i: f64 = dot(wo, h);
+	let sin2_t: f64 = (1.0 - cos_i * cos_i).max(0.0) / (eta * eta);
+	if sin2_t >= 1.0 {
+		return None;
+	}
+	let cos_t: f64 = (1.0 - sin2_t).sqrt();
+	Some(-wo / eta + (cos_i / eta - cos_t) * h)
+}
@@ -1,21 +1,38 @@
 mod lambertian;
 mod metal;
+mod conductor;
+mod microfacet;
 mod dielectric;
+mod diffuse_light;
+mod principled;
+mod isotropic;
+mod henyey_greenstein;
 
 use crate::{
-	color::Color,
+	color::{Color, BLACK, WHITE},
 	ray::Ray,
-	hit::HitRecord
+	hit::HitRecord,
+	sampler::Sampler,
+	vec::Vec3
 };
 
+/// Direction sampled by a material. The attenuation is the BSDF times the cosine
+/// over the pdf, the weight that carries the light of the scattered ray back
 pub struct MaterialRayInteraction {
 	attenuation: Color,
 	scattered: Ray,
+	pdf: f64,			// Density of the scattered direction, in solid angle
+	is_specular: bool,	// Sampled from a delta lobe, which cannot be evaluated
 }
 
 impl MaterialRayInteraction {
-	pub fn new(attenuation: Color, scattered: Ray) -> MaterialRayInteraction {
-		MaterialRayInteraction { attenuation: attenuation, scattered: scattered }
+	pub fn new(attenuation: Color, scattered: Ray, pdf: f64) -> MaterialRayInteraction {
+		MaterialRayInteraction { attenuation, scattered, pdf, is_specular: false }
+	}
+
+	/// Interaction with a perfect mirror or refraction, the pdf is a delta
+	pub fn specular(attenuation: Color, scattered: Ray) -> MaterialRayInteraction {
+		MaterialRayInteraction { attenuation, scattered, pdf: 0.0, is_specular: true }
 	}
 
 	pub fn attenuation(&self) -> Color {
@@ -25,12 +42,60 @@ impl MaterialRayInteraction {
 	pub fn scattered(&self) -> Ray {
 		self.scattered
 	}
+
+	pub fn pdf(&self) -> f64 {
+		self.pdf
+	}
+
+	pub fn is_specular(&self) -> bool {
+		self.is_specular
+	}
 }
 
-pub trait Material {
-	fn scatter(&self, ray_in: &Ray, hit_record: &HitRecord) -> Option<MaterialRayInteraction>;
+pub trait Material: Send + Sync {
+	fn scatter(&self, ray_in: &Ray, hit_record: &HitRecord, sampler: &mut dyn Sampler) -> Option<MaterialRayInteraction>;
+
+	/// BSDF for light arriving along the unit direction `wi` and leaving towards `wo`,
+	/// both pointing away from the surface. Specular lobes are not included
+	fn eval(&self, _hit_record: &HitRecord, _wi: Vec3, _wo: Vec3) -> Color {
+		BLACK
+	}
+
+	/// Density, in solid angle, with which `scatter` samples `wi` when the light
+	/// leaves towards `wo`. Specular lobes are not included
+	fn pdf(&self, _hit_record: &HitRecord, _wi: Vec3, _wo: Vec3) -> f64 {
+		0.0
+	}
+
+	/// Radiance emitted at the hit point towards the incoming ray. Most materials do not emit
+	fn emitted(&self, _ray_in: &Ray, _hit_record: &HitRecord) -> Color {
+		BLACK
+	}
+
+	/// Fraction of the light crossing `distance` inside the object, for rays hitting
+	/// its surface from the inside. Most materials are opaque or perfectly clear
+	fn transmittance(&self, _hit_record: &HitRecord, _distance: f64) -> Color {
+		WHITE
+	}
+
+	/// Whether the light scatters at a point inside a medium rather than off a surface.
+	/// The phase function of a medium is not weighted by a cosine with the normal
+	fn is_volumetric(&self) -> bool {
+		false
+	}
+
+	/// Share of the light a medium scatters at each collision, the rest is absorbed.
+	/// Only the phase functions of media have one
+	fn scattering_albedo(&self, _hit_record: &HitRecord) -> Color {
+		WHITE
+	}
 }
 
 pub use lambertian::Lambertian;
 pub use metal::Metal;
-pub use dielectric::Dielectric;
\ No newline at end of file
+pub use conductor::{Conductor, ConductorPreset};
+pub use dielectric::Dielectric;
+pub use diffuse_light::DiffuseLight;
+pub use principled::Principled;
+pub use isotropic::Isotropic;
+pub use henyey_greenstein::HenyeyGreenstein;
\ No newline at end of file
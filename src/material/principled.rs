@@ -0,0 +1,375 @@
+use crate::{
+	material::{Material, MaterialRayInteraction, microfacet::TrowbridgeReitz},
+	ray::Ray,
+	hit::HitRecord,
+	sampler::Sampler,
+	onb::Onb,
+	vec::{Vec3, reflect, dot},
+	color::{Color, BLACK, WHITE, luminance},
+	texture::{Texture, SolidColor}
+};
+
+use std::{f64::consts::PI, sync::Arc};
+
+// Smoother surfaces would need perfect mirror lobes, which cannot be blended
+const MIN_ROUGHNESS: f64 = 0.04;
+
+/// Principled material in the manner of the Disney BSDF, blending a diffuse base
+/// with sheen, a specular reflection, a rough glass transmission and a clearcoat
+/// from a few artist-friendly parameters:
+///
+/// - `base_color`: color of the diffuse base, of metals and of the glass
+/// - `metallic`: 0 for dielectrics, 1 for metals reflecting the base color
+/// - `roughness`: spread of the specular reflections and of the glass, 0 to 1
+/// - `specular`: reflectance of dielectrics, 0.5 matching an index of refraction of 1.5
+/// - `transmission`: share of the dielectric turned into glass
+/// - `ior`: index of refraction of the glass
+/// - `clearcoat`: strength of a glossy white layer over everything, with its own
+///   `clearcoat_roughness`
+/// - `sheen`: soft brightening at grazing angles, as on cloth
+/// - `emission`: radiance added to the reflected light, none unless given
+///
+/// Every parameter is a texture, numbers being read from the mean of the channels
+pub struct Principled {
+	base_color: Arc<dyn Texture>,
+	metallic: Arc<dyn Texture>,
+	roughness: Arc<dyn Texture>,
+	specular: Arc<dyn Texture>,
+	transmission: Arc<dyn Texture>,
+	ior: Arc<dyn Texture>,
+	clearcoat: Arc<dyn Texture>,
+	clearcoat_roughness: Arc<dyn Texture>,
+	sheen: Arc<dyn Texture>,
+	emission: Option<Arc<dyn Texture>>,
+}
+
+impl Principled {
+	/// Rough dielectric of the given base color, with the defaults of the other parameters:
+	/// no metal, roughness 0.5, specular 0.5, no transmission, ior 1.5, no clearcoat
+	/// (roughness 0.1 when added) and no sheen
+	pub fn new(base_color: Arc<dyn Texture>) -> Principled {
+		Principled {
+			base_color,
+			metallic: grey(0.0),
+			roughness: grey(0.5),
+			specular: grey(0.5),
+			transmission: grey(0.0),
+			ior: grey(1.5),
+			clearcoat: grey(0.0),
+			clearcoat_roughness: grey(0.1),
+			sheen: grey(0.0),
+			emission: None
+		}
+	}
+
+	pub fn with_metallic(mut self, metallic: Arc<dyn Texture>) -> Principled {
+		self.metallic = metallic;
+		self
+	}
+
+	pub fn with_roughness(mut self, roughness: Arc<dyn Texture>) -> Principled {
+		self.roughness = roughness;
+		self
+	}
+
+	pub fn with_specular(mut self, specular: Arc<dyn Texture>) -> Principled {
+		self.specular = specular;
+		self
+	}
+
+	pub fn with_transmission(mut self, transmission: Arc<dyn Texture>) -> Principled {
+		self.transmission = transmission;
+		self
+	}
+
+	pub fn with_ior(mut self, ior: Arc<dyn Texture>) -> Principled {
+		self.ior = ior;
+		self
+	}
+
+	pub fn with_clearcoat(mut self, clearcoat: Arc<dyn Texture>) -> Principled {
+		self.clearcoat = clearcoat;
+		self
+	}
+
+	pub fn with_clearcoat_roughness(mut self, clearcoat_roughness: Arc<dyn Texture>) -> Principled {
+		self.clearcoat_roughness = clearcoat_roughness;
+		self
+	}
+
+	pub fn with_sheen(mut self, sheen: Arc<dyn Texture>) -> Principled {
+		self.sheen = sheen;
+		self
+	}
+
+	pub fn with_emission(mut self, emission: Arc<dyn Texture>) -> Principled {
+		self.emission = Some(emission);
+		self
+	}
+
+	// Parameters at the hit point
+	fn lobes(&self, hit_record: &HitRecord) -> Lobes {
+		let color = |texture: &Arc<dyn Texture>| -> Color {
+			texture.filtered(hit_record.u, hit_record.v, hit_record.p, hit_record.uv_footprint)
+		};
+		let scalar = |texture: &Arc<dyn Texture>| -> f64 {
+			let value: Color = color(texture);
+			(value.x + value.y + value.z) / 3.0
+		};
+
+		let base_color: Color = color(&self.base_color) * hit_record.color;
+		let metallic: f64 = scalar(&self.metallic).clamp(0.0, 1.0);
+		let roughness: f64 = scalar(&self.roughness).clamp(MIN_ROUGHNESS, 1.0);
+		let transmission: f64 = scalar(&self.transmission).clamp(0.0, 1.0);
+		let ior: f64 = scalar(&self.ior).max(1.0 + 1e-4);
+
+		Lobes {
+			base_color,
+			roughness,
+			metallic,
+			dielectric_f0: 0.08 * scalar(&self.specular).clamp(0.0, 1.0),
+			eta: if hit_record.front_face { ior } else { 1.0 / ior },
+			distribution: TrowbridgeReitz::from_roughness(roughness),
+			clearcoat_distribution: TrowbridgeReitz::from_roughness(scalar(&self.clearcoat_roughness).clamp(MIN_ROUGHNESS, 1.0)),
+			diffuse_weight: (1.0 - metallic) * (1.0 - transmission),
+			glass_weight: (1.0 - metallic) * transmission,
+			clearcoat: 0.25 * scalar(&self.clearcoat).max(0.0),
+			sheen: scalar(&self.sheen).max(0.0)
+		}
+	}
+}
+
+fn grey(value: f64) -> Arc<dyn Texture> {
+	Arc::new(SolidColor::new(Color::new(value, value, value)))
+}
+
+fn schlick_weight(cosine: f64) -> f64 {
+	(1.0 - cosine.clamp(0.0, 1.0)).powi(5)
+}
+
+// Parameters of the material at a point, directions in the local frame of the surface
+struct Lobes {
+	base_color: Color,
+	roughness: f64,
+	metallic: f64,
+	dielectric_f0: f64,				// Reflectance of the dielectric base at normal incidence
+	eta: f64,						// Index of refraction below the surface over the one above
+	distribution: TrowbridgeReitz,
+	clearcoat_distribution: TrowbridgeReitz,
+	diffuse_weight: f64,
+	glass_weight: f64,
+	clearcoat: f64,
+	sheen: f64,
+}
+
+impl Lobes {
+	// Reflectance of the specular lobe, coloured by the base color on metals. Glass
+	// reflects through its own lobe
+	fn specular_fresnel(&self, cosine: f64) -> Color {
+		let weight: f64 = schlick_weight(cosine);
+		let metal: Color = self.base_color + weight * (WHITE - self.base_color);
+		self.metallic * metal + (self.diffuse_weight * self.dielectric_fresnel(cosine)) * WHITE
+	}
+
+	fn dielectric_fresnel(&self, cosine: f64) -> f64 {
+		self.dielectric_f0 + schlick_weight(cosine) * (1.0 - self.dielectric_f0)
+	}
+
+	fn clearcoat_fresnel(&self, cosine: f64) -> f64 {
+		self.clearcoat * (0.04 + 0.96 * schlick_weight(cosine))
+	}
+
+	// Probabilities of sampling the diffuse, specular, glass and clearcoat lobes, about
+	// the share of the light each one reflects towards `wo`
+	fn probabilities(&self, wo: Vec3) -> [f64; 4] {
+		let below_clearcoat: f64 = 1.0 - self.clearcoat_fresnel(wo.z);
+		let weights: [f64; 4] = [
+			below_clearcoat * self.diffuse_weight * (1.0 - self.dielectric_fresnel(wo.z)) * (luminance(self.base_color) + self.sheen),
+			below_clearcoat * (luminance(self.specular_fresnel(wo.z)) + 0.05 * (self.metallic + self.diffuse_weight)),
+			below_clearcoat * self.glass_weight,
+			self.clearcoat_fresnel(wo.z)
+		];
+
+		let total: f64 = weights.iter().sum();
+		if total <= 0.0 {
+			return [0.0; 4];
+		}
+		weights.map(|weight| weight / total)
+	}
+
+	fn eval(&self, wi: Vec3, wo: Vec3) -> Color {
+		if wo.z <= 0.0 || wi.z == 0.0 {
+			return BLACK;
+		}
+
+		// Layers below the clearcoat and the specular reflection only receive the light
+		// these did not reflect, on the way in and on the way out
+		let mut bsdf: Color = BLACK;
+		if wi.z > 0.0 {
+			let h: Vec3 = (wi + wo).unit_vector();
+			let cos_d: f64 = dot(wi, h);
+
+			// Burley's diffuse, with a retro-reflection growing with the roughness, and
+			// the sheen tinted halfway towards the hue of the base color
+			if self.diffuse_weight > 0.0 {
+				let fd90: f64 = 0.5 + 2.0 * self.roughness * cos_d * cos_d;
+				let diffuse: f64 = (1.0 + (fd90 - 1.0) * schlick_weight(wi.z)) * (1.0 + (fd90 - 1.0) * schlick_weight(wo.z)) / PI;
+				let brightness: f64 = luminance(self.base_color);
+				let tint: Color = if brightness > 0.0 { self.base_color / brightness } else { WHITE };
+				let sheen: Color = (self.sheen * schlick_weight(cos_d)) * (0.5 * (WHITE + tint));
+				let transmitted: f64 = (1.0 - self.dielectric_fresnel(wi.z)) * (1.0 - self.dielectric_fresnel(wo.z));
+				bsdf += (self.diffuse_weight * transmitted) * (diffuse * self.base_color + sheen);
+			}
+
+			if self.glass_weight < 1.0 {
+				let specular: f64 = self.distribution.d(h) * self.distribution.g(wo, wi) / (4.0 * wo.z * wi.z);
+				bsdf += specular * self.specular_fresnel(cos_d);
+			}
+			bsdf = ((1.0 - self.clearcoat_fresnel(wi.z)) * (1.0 - self.clearcoat_fresnel(wo.z))) * bsdf;
+
+			if self.clearcoat > 0.0 {
+				let clearcoat: f64 = self.clearcoat_distribution.d(h) * self.clearcoat_distribution.g(wo, wi) / (4.0 * wo.z * wi.z);
+				let clearcoat: f64 = self.clearcoat_fresnel(cos_d) * clearcoat;
+				bsdf += Color::new(clearcoat, clearcoat, clearcoat);
+			}
+		}
+
+		// Light refracted through the glass is tinted by the base color, half on the
+		// way in and half on the way out. The clearcoat only covers the side facing `wo`
+		if self.glass_weight > 0.0 {
+			let glass: f64 = self.glass_weight * self.distribution.dielectric_bsdf(wo, wi, self.eta) * (1.0 - self.clearcoat_fresnel(wo.z));
+			bsdf += if wi.z > 0.0 { (1.0 - self.clearcoat_fresnel(wi.z)) * Color::new(glass, glass, glass) } else { glass * self.base_color.sqrt() };
+		}
+		bsdf
+	}
+
+	fn pdf(&self, wi: Vec3, wo: Vec3) -> f64 {
+		if wo.z <= 0.0 || wi.z == 0.0 {
+			return 0.0;
+		}
+
+		let [diffuse, specular, glass, clearcoat] = self.probabilities(wo);
+		let mut pdf: f64 = glass * self.distribution.dielectric_pdf(wo, wi, self.eta);
+		if wi.z > 0.0 {
+			let h: Vec3 = (wi + wo).unit_vector();
+			pdf += diffuse * wi.z / PI
+				+ specular * self.distribution.visible_normal_pdf(wo, h) / (4.0 * dot(wo, h))
+				+ clearcoat * self.clearcoat_distribution.visible_normal_pdf(wo, h) / (4.0 * dot(wo, h));
+		}
+		pdf
+	}
+
+	// Direction drawn from one of the lobes, picked by its probability
+	fn sample(&self, wo: Vec3, sampler: &mut dyn Sampler) -> Option<Vec3> {
+		let [diffuse, specular, glass, _] = self.probabilities(wo);
+		let u: f64 = sampler.get_1d();
+		let wi: Vec3 = if u < diffuse {
+			Vec3::random_cosine_direction(sampler)
+		} else if u < diffuse + specular {
+			reflect(-wo, self.distribution.sample_visible_normal(wo, sampler.get_2d()))
+		} else if u < diffuse + specular + glass {
+			// The lobe choice is stretched back to [0, 1) to also pick reflection or refraction
+			let u_lobe: f64 = ((u - diffuse - specular) / glass).min(1.0 - f64::EPSILON);
+			self.distribution.sample_dielectric(wo, self.eta, sampler.get_2d(), u_lobe)?
+		} else {
+			reflect(-wo, self.clearcoat_distribution.sample_visible_normal(wo, sampler.get_2d()))
+		};
+
+		if wi.z == 0.0 { None } else { Some(wi) }
+	}
+}
+
+impl Material for Principled {
+	fn scatter(&self, ray_in: &Ray, hit_record: &HitRecord, sampler: &mut dyn Sampler) -> Option<MaterialRayInteraction> {
+		let lobes: Lobes = self.lobes(hit_record);
+		let onb: Onb = Onb::new(hit_record.normal);
+		let wo: Vec3 = onb.to_local(-ray_in.dir().unit_vector());
+		if wo.z <= 0.0 {
+			return None;
+		}
+
+		// Every lobe could have drawn the direction, so the weight uses the full BSDF and pdf
+		let wi: Vec3 = lobes.sample(wo, sampler)?;
+		let pdf: f64 = lobes.pdf(wi, wo);
+		if pdf <= 0.0 {
+			return None;
+		}
+		let attenuation: Color = (wi.z.abs() / pdf) * lobes.eval(wi, wo);
+		Some(MaterialRayInteraction::new(attenuation, Ray::new(hit_record.p, onb.local(wi)), pdf))
+	}
+
+	fn eval(&self, hit_record: &HitRecord, wi: Vec3, wo: Vec3) -> Color {
+		let onb: Onb = Onb::new(hit_record.normal);
+		self.lobes(hit_record).eval(onb.to_local(wi), onb.to_local(wo))
+	}
+
+	fn pdf(&self, hit_record: &HitRecord, wi: Vec3, wo: Vec3) -> f64 {
+		let onb: Onb = Onb::new(hit_record.normal);
+		self.lobes(hit_record).pdf(onb.to_local(wi), onb.to_local(wo))
+	}
+
+	fn emitted(&self, _ray_in: &Ray, hit_record: &HitRecord) -> Color {
+		match &self.emission {
+			Some(emission) => emission.filtered(hit_record.u, hit_record.v, hit_record.p, hit_record.uv_footprint),
+			None => BLACK
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{
+		sampler::SamplerKind,
+		vec::Point3
+	};
+
+	// Share of the light reflected or transmitted towards `wo` under uniform white
+	// lighting, averaged over the directions drawn by the material
+	fn albedo(material: Arc<Principled>, wo: Vec3, sampler: &mut dyn Sampler) -> Color {
+		let hit_record: HitRecord = HitRecord {
+			p: Point3::zeros(),
+			normal: Vec3::new(0.0, 0.0, 1.0),
+			t: 1.0,
+			u: 0.0,
+			v: 0.0,
+			dpdu: Vec3::new(1.0, 0.0, 0.0),
+			dpdv: Vec3::new(0.0, 1.0, 0.0),
+			uv_footprint: 0.0,
+			front_face: true,
+			color: WHITE,
+			material: material.clone()
+		};
+		let ray: Ray = Ray::new(Point3::from(wo), -wo);
+
+		let samples: usize = 20000;
+		let mut total: Color = BLACK;
+		for _ in 0..samples {
+			if let Some(interaction) = material.scatter(&ray, &hit_record, sampler) {
+				total += interaction.attenuation;
+			}
+		}
+		total / samples as f64
+	}
+
+	// Sweep of the material preview scene, with a white base color
+	#[test]
+	fn white_furnace_does_not_gain_energy() {
+		let mut sampler: Box<dyn Sampler> = SamplerKind::Independent.build(3, 1);
+		for metallic in [0.0, 0.25, 0.5, 0.75, 1.0] {
+			for roughness in [0.0, 0.25, 0.5, 0.75, 1.0] {
+				let material: Arc<Principled> = Arc::new(Principled::new(grey(1.0))
+					.with_metallic(grey(metallic))
+					.with_roughness(grey(roughness)));
+				for cos_theta in [1.0, 0.7, 0.4, 0.1_f64] {
+					let wo: Vec3 = Vec3::new((1.0 - cos_theta * cos_theta).sqrt(), 0.0, cos_theta);
+					let albedo: Color = albedo(material.clone(), wo, sampler.as_mut());
+					assert!(
+						albedo.x.max(albedo.y).max(albedo.z) <= 1.02,
+						"albedo {} with metallic {}, roughness {} and cos theta {}", albedo, metallic, roughness, cos_theta
+					);
+				}
+			}
+		}
+	}
+}
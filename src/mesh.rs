@@ -0,0 +1,138 @@
+use std::sync::Arc;
+
+use crate::{
+	aabb::Aabb,
+	bvh::Bvh,
+	color::Color,
+	hit::{HitRecord, Hitable},
+	material::Material,
+	ray::Ray,
+	sampler::Sampler,
+	triangle::{Uv, intersect_triangle, triangle_bounding_box, triangle_hit_record},
+	vec::{Point3, Vec3}
+};
+
+/// Triangles sharing vertex buffers. Each triangle is three indices into the
+/// buffers, and the whole mesh is traced through its own BVH
+pub struct TriangleMesh {
+	positions: Vec<Point3>,
+	normals: Option<Vec<Vec3>>,
+	uvs: Option<Vec<Uv>>,
+	colors: Option<Vec<Color>>,
+	indices: Vec<[u32; 3]>,
+	material: Arc<dyn Material>,
+	bvh: Bvh,
+}
+
+impl TriangleMesh {
+	/// Fails if an index is out of the vertex buffer
+	pub fn new(positions: Vec<Point3>, indices: Vec<[u32; 3]>, material: Arc<dyn Material>) -> Result<TriangleMesh, String> {
+		if let Some(index) = indices.iter().flatten().find(|i| **i as usize >= positions.len()) {
+			return Err(format!("face index {} out of range, {} vertices defined", index, positions.len()));
+		}
+
+		let bounds: Vec<Aabb> = indices.iter()
+			.map(|triangle| triangle_bounding_box(triangle.map(|i| positions[i as usize])))
+			.collect();
+
+		Ok(TriangleMesh {
+			bvh: Bvh::new(&bounds),
+			positions,
+			normals: None,
+			uvs: None,
+			colors: None,
+			indices,
+			material
+		})
+	}
+
+	/// Per-vertex shading normals. Fails unless there is one for each position
+	pub fn with_normals(mut self, normals: Vec<Vec3>) -> Result<TriangleMesh, String> {
+		self.check_vertex_count(normals.len(), "normals")?;
+		self.normals = Some(normals);
+		Ok(self)
+	}
+
+	/// Per-vertex texture coordinates. Fails unless there is one for each position
+	pub fn with_uvs(mut self, uvs: Vec<Uv>) -> Result<TriangleMesh, String> {
+		self.check_vertex_count(uvs.len(), "texture coordinates")?;
+		self.uvs = Some(uvs);
+		Ok(self)
+	}
+
+	/// Per-vertex colors scaling the albedo of the material. Fails unless there is one
+	/// for each position
+	pub fn with_colors(mut self, colors: Vec<Color>) -> Result<TriangleMesh, String> {
+		self.check_vertex_count(colors.len(), "colors")?;
+		self.colors = Some(colors);
+		Ok(self)
+	}
+
+	/// Same triangles with another material
+	pub fn with_material(mut self, material: Arc<dyn Material>) -> TriangleMesh {
+		self.material = material;
+		self
+	}
+
+	pub fn vertex_count(&self) -> usize {
+		self.positions.len()
+	}
+
+	pub fn triangle_count(&self) -> usize {
+		self.indices.len()
+	}
+
+	fn check_vertex_count(&self, count: usize, attribute: &str) -> Result<(), String> {
+		if count != self.positions.len() {
+			return Err(format!("{} {} for {} vertices", count, attribute, self.positions.len()));
+		}
+		Ok(())
+	}
+
+	fn hit_triangle(&self, index: usize, ray: &Ray, ray_tmin: f64, ray_tmax: f64) -> Option<HitRecord> {
+		let triangle: [usize; 3] = self.indices[index].map(|i| i as usize);
+		let positions: [Point3; 3] = triangle.map(|i| self.positions[i]);
+
+		let (t, b1, b2) = intersect_triangle(ray, positions[0], positions[1], positions[2], ray_tmin, ray_tmax)?;
+		Some(triangle_hit_record(
+			ray, t, b1, b2, 
+			positions,
+			self.normals.as_ref().map(|normals| triangle.map(|i| normals[i])),
+			self.uvs.as_ref().map(|uvs| triangle.map(|i| uvs[i])),
+			self.colors.as_ref().map(|colors| triangle.map(|i| colors[i])),
+			&self.material
+		))
+	}
+}
+
+impl Hitable for TriangleMesh {
+	fn hit(&self, ray: &Ray, ray_tmin: f64, ray_tmax: f64, _sampler: &mut dyn Sampler) -> Option<HitRecord> {
+		self.bvh.hit(ray, ray_tmin, ray_tmax, |index, ray, t_min, t_max| {
+			self.hit_triangle(index, ray, t_min, t_max)
+		})
+	}
+
+	fn bounding_box(&self) -> Aabb {
+		self.bvh.bounding_box()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{color::WHITE, material::Lambertian};
+
+	#[test]
+	fn bad_buffers_are_errors() {
+		let material: Arc<dyn Material> = Arc::new(Lambertian::new(WHITE));
+		let positions: Vec<Point3> = vec![Point3::zeros(), Point3::new(1.0, 0.0, 0.0), Point3::new(0.0, 1.0, 0.0)];
+
+		assert!(TriangleMesh::new(positions.clone(), vec![[0, 1, 3]], material.clone()).is_err());
+
+		let mesh = || TriangleMesh::new(positions.clone(), vec![[0, 1, 2]], material.clone()).expect("valid mesh");
+		assert!(mesh().with_normals(vec![Vec3::new(0.0, 0.0, 1.0); 2]).is_err());
+		assert!(mesh().with_uvs(vec![[0.0, 0.0]; 4]).is_err());
+		assert!(mesh().with_colors(Vec::new()).is_err());
+		assert!(mesh().with_normals(vec![Vec3::new(0.0, 0.0, 1.0); 3]).and_then(|mesh| mesh.with_colors(vec![WHITE; 3])).is_ok());
+	}
+}
@@ -0,0 +1,456 @@
+use std::{
+	collections::{BTreeMap, HashMap},
+	fmt,
+	fs,
+	path::{Path, PathBuf},
+	sync::Arc
+};
+
+use crate::{
+	color::Color,
+	material::{Material, Lambertian, Conductor, Dielectric},
+	texture::{ImageTexture, ColorSpace, SolidColor},
+	mesh::TriangleMesh,
+	triangle::Uv,
+	vec::{Point3, Vec3}
+};
+
+/// Triangles of an OBJ file sharing a group and a material
+pub struct ObjGroup {
+	pub name: String,
+	pub material_name: Option<String>,
+	pub mesh: TriangleMesh,
+}
+
+/// Content of an OBJ file, with the warnings about what was not imported
+pub struct ObjModel {
+	pub groups: Vec<ObjGroup>,
+	pub warnings: Vec<String>,
+}
+
+#[derive(Debug)]
+pub enum ObjError {
+	Io(PathBuf, std::io::Error),
+	Parse { path: PathBuf, line: usize, message: String },
+}
+
+impl fmt::Display for ObjError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			ObjError::Io(path, err) => write!(f, "{}: {}", path.display(), err),
+			ObjError::Parse { path, line, message } =>
+				write!(f, "{}:{}: {}", path.display(), line, message)
+		}
+	}
+}
+
+impl std::error::Error for ObjError {}
+
+const GLASS_OPACITY: f64 = 0.5;		// Surfaces less opaque than this become glass
+
+/// Material statements of an MTL file the renderer understands
+#[derive(Clone)]
+struct MtlMaterial {
+	kd: Color,				// Diffuse color
+	ks: Color,				// Specular color
+	ns: f64,				// Specular exponent
+	ni: f64,				// Index of refraction
+	d: f64,					// Opacity
+	illum: u32,				// Illumination model
+	map_kd: Option<Arc<ImageTexture>>,	// Diffuse texture, replacing the diffuse color
+}
+
+impl Default for MtlMaterial {
+	fn default() -> MtlMaterial {
+		MtlMaterial {
+			kd: Color::new(0.8, 0.8, 0.8),
+			ks: Color::zeros(),
+			ns: 0.0,
+			ni: 1.5,
+			d: 1.0,
+			illum: 2,
+			map_kd: None
+		}
+	}
+}
+
+impl MtlMaterial {
+	// Illumination models with refraction, or surfaces mostly see-through. Slightly
+	// transparent ones stay opaque rather than turn into clear glass
+	fn is_glass(&self) -> bool {
+		self.d < GLASS_OPACITY || matches!(self.illum, 4 | 6 | 7 | 9)
+	}
+
+	// Specular color brighter than the diffuse one
+	fn is_metal(&self) -> bool {
+		let max_component = |c: Color| c.x.max(c.y).max(c.z);
+		max_component(self.ks) > max_component(self.kd)
+	}
+
+	// Closest renderer material: transparent surfaces become glass, surfaces with a
+	// specular color brighter than the diffuse one become metal, the rest are diffuse
+	fn build(&self) -> Arc<dyn Material> {
+		if self.is_glass() {
+			Arc::new(Dielectric::new(if self.ni > 0.0 { self.ni } else { 1.5 }))
+		} else if self.is_metal() {
+			// Usual conversion from a Phong exponent to a lobe width, the square of the roughness
+			let alpha: f64 = (2.0 / (self.ns.max(0.0) + 2.0)).sqrt();
+			Arc::new(Conductor::from_reflectance(Arc::new(SolidColor::new(self.ks)), alpha.sqrt()))
+		} else if let Some(texture) = &self.map_kd {
+			Arc::new(Lambertian::from_texture(texture.clone()))
+		} else {
+			Arc::new(Lambertian::new(self.kd))
+		}
+	}
+}
+
+// Collects the warnings, reporting each unsupported statement once per file
+struct Warnings {
+	path: PathBuf,
+	unsupported: BTreeMap<String, (usize, usize)>,	// Statement -> (first line, count)
+	messages: Vec<String>,
+}
+
+impl Warnings {
+	fn new(path: &Path) -> Warnings {
+		Warnings { path: path.to_path_buf(), unsupported: BTreeMap::new(), messages: Vec::new() }
+	}
+
+	fn unsupported(&mut self, statement: &str, line: usize) {
+		self.unsupported.entry(statement.to_string()).or_insert((line, 0)).1 += 1;
+	}
+
+	fn warn(&mut self, line: usize, message: String) {
+		self.messages.push(format!("{}:{}: {}", self.path.display(), line, message));
+	}
+
+	fn finish(mut self, warnings: &mut Vec<String>) {
+		for (statement, (line, count)) in self.unsupported.iter() {
+			let times: String = if *count > 1 { format!(" ({} times)", count) } else { String::new() };
+			self.messages.push(format!("{}:{}: unsupported statement '{}' ignored{}",
+				self.path.display(), line, statement, times));
+		}
+		warnings.append(&mut self.messages);
+	}
+}
+
+// Vertex of a face as indices into the position, uv and normal lists
+type FaceVertex = (usize, Option<usize>, Option<usize>);
+
+// Faces of the group being read, as triangles of face vertices
+struct GroupBuilder {
+	line: usize,			// Line of the first face, where mesh errors are reported
+	triangles: Vec<[FaceVertex; 3]>,
+}
+
+pub fn load_obj(path: &Path) -> Result<ObjModel, ObjError> {
+	let source: String = fs::read_to_string(path)
+		.map_err(|err| ObjError::Io(path.to_path_buf(), err))?;
+
+	let mut warnings: Warnings = Warnings::new(path);
+	let mut all_warnings: Vec<String> = Vec::new();
+	let base_dir: &Path = path.parent().unwrap_or(Path::new(""));
+	let parse_error = |line: usize, message: String| ObjError::Parse { path: path.to_path_buf(), line, message };
+
+	let mut positions: Vec<Point3> = Vec::new();
+	let mut uvs: Vec<Uv> = Vec::new();
+	let mut normals: Vec<Vec3> = Vec::new();
+	let mut materials: HashMap<String, MtlMaterial> = HashMap::new();
+
+	// Faces are kept per (group, material) in the order they first appear
+	let mut groups: Vec<((String, Option<String>), GroupBuilder)> = Vec::new();
+	let mut group_name: String = "default".to_string();
+	let mut material_name: Option<String> = None;
+
+	for (line_index, line) in source.lines().enumerate() {
+		let line_number: usize = line_index + 1;
+		let line: &str = line.split('#').next().unwrap_or("").trim();
+		let mut tokens = line.split_whitespace();
+		let statement: &str = match tokens.next() {
+			Some(statement) => statement,
+			None => continue
+		};
+		let arguments: Vec<&str> = tokens.collect();
+
+		match statement {
+			"v" => positions.push(parse_vec3(&arguments).map_err(|err| parse_error(line_number, err))?),
+			"vn" => normals.push(parse_vec3(&arguments).map_err(|err| parse_error(line_number, err))?),
+			"vt" => {
+				let uv: Vec<f64> = parse_numbers(&arguments).map_err(|err| parse_error(line_number, err))?;
+				match uv[..] {
+					[u] => uvs.push([u, 0.0]),
+					[u, v, ..] => uvs.push([u, v]),
+					_ => return Err(parse_error(line_number, "texture coordinate without values".to_string()))
+				}
+			},
+			"f" => {
+				if arguments.len() < 3 {
+					return Err(parse_error(line_number, format!("face with {} vertices", arguments.len())));
+				}
+				let face: Vec<FaceVertex> = arguments.iter()
+					.map(|vertex| parse_face_vertex(vertex, positions.len(), uvs.len(), normals.len()))
+					.collect::<Result<Vec<FaceVertex>, String>>()
+					.map_err(|err| parse_error(line_number, err))?;
+
+				let key: (String, Option<String>) = (group_name.clone(), material_name.clone());
+				let group: &mut GroupBuilder = match groups.iter().position(|(k, _)| *k == key) {
+					Some(index) => &mut groups[index].1,
+					None => {
+						groups.push((key, GroupBuilder { line: line_number, triangles: Vec::new() }));
+						&mut groups.last_mut().unwrap().1
+					}
+				};
+
+				// Polygons are split in a fan around their first vertex
+				for i in 1..face.len() - 1 {
+					group.triangles.push([face[0], face[i], face[i + 1]]);
+				}
+			},
+			"g" | "o" => {
+				group_name = if arguments.is_empty() { "default".to_string() } else { arguments.join(" ") };
+			},
+			"usemtl" => {
+				let name: String = arguments.join(" ");
+				if !materials.contains_key(&name) {
+					warnings.warn(line_number, format!("material '{}' not defined, using the default one", name));
+				}
+				material_name = Some(name);
+			},
+			"mtllib" => {
+				for library in arguments.iter() {
+					let mtl_path: PathBuf = base_dir.join(library);
+					match load_mtl(&mtl_path, &mut all_warnings) {
+						Ok(library_materials) => materials.extend(library_materials),
+						Err(err) => warnings.warn(line_number, format!("material library not loaded: {}", err))
+					}
+				}
+			},
+			// Smoothing groups do not matter, normals come from the file
+			"s" => {},
+			_ => warnings.unsupported(statement, line_number)
+		}
+	}
+
+	let mut model_groups: Vec<ObjGroup> = Vec::new();
+	for ((name, material_name), group) in groups.into_iter() {
+		let material: Arc<dyn Material> = material_name.as_ref()
+			.and_then(|name| materials.get(name))
+			.cloned()
+			.unwrap_or_default()
+			.build();
+		let mesh: TriangleMesh = build_mesh(&group, &positions, &uvs, &normals, material)
+			.map_err(|err| parse_error(group.line, err))?;
+		model_groups.push(ObjGroup { name, material_name, mesh });
+	}
+
+	warnings.finish(&mut all_warnings);
+	Ok(ObjModel {
+		groups: model_groups,
+		warnings: all_warnings
+	})
+}
+
+fn load_mtl(path: &Path, all_warnings: &mut Vec<String>) -> Result<HashMap<String, MtlMaterial>, ObjError> {
+	let source: String = fs::read_to_string(path)
+		.map_err(|err| ObjError::Io(path.to_path_buf(), err))?;
+
+	let mut warnings: Warnings = Warnings::new(path);
+	let parse_error = |line: usize, message: String| ObjError::Parse { path: path.to_path_buf(), line, message };
+
+	let mut materials: HashMap<String, MtlMaterial> = HashMap::new();
+	let mut current: Option<(String, usize, MtlMaterial)> = None;
+	let mut textures: HashMap<PathBuf, Arc<ImageTexture>> = HashMap::new();
+
+	for (line_index, line) in source.lines().enumerate() {
+		let line_number: usize = line_index + 1;
+		let line: &str = line.split('#').next().unwrap_or("").trim();
+		let mut tokens = line.split_whitespace();
+		let statement: &str = match tokens.next() {
+			Some(statement) => statement,
+			None => continue
+		};
+		let arguments: Vec<&str> = tokens.collect();
+
+		if statement == "newmtl" {
+			if let Some(finished) = current.take() {
+				add_material(&mut materials, &mut warnings, finished);
+			}
+			current = Some((arguments.join(" "), line_number, MtlMaterial::default()));
+			continue;
+		}
+
+		let material: &mut MtlMaterial = match current.as_mut() {
+			Some((_, _, material)) => material,
+			None => {
+				warnings.warn(line_number, format!("'{}' before any newmtl ignored", statement));
+				continue;
+			}
+		};
+
+		let number = |arguments: &[&str]| -> Result<f64, ObjError> {
+			parse_numbers(arguments)
+				.and_then(|numbers| numbers.first().copied().ok_or_else(|| "missing value".to_string()))
+				.map_err(|err| parse_error(line_number, err))
+		};
+
+		match statement {
+			"Kd" => material.kd = parse_color(&arguments).map_err(|err| parse_error(line_number, err))?,
+			"Ks" => material.ks = parse_color(&arguments).map_err(|err| parse_error(line_number, err))?,
+			"Ns" => material.ns = number(&arguments)?,
+			"Ni" => material.ni = number(&arguments)?,
+			"d" => material.d = number(&arguments)?,
+			"Tr" => material.d = 1.0 - number(&arguments)?,
+			"illum" => material.illum = number(&arguments)? as u32,
+			// Options come before the file name, none of them is supported
+			"map_Kd" => {
+				let file: &str = arguments.last().ok_or_else(|| parse_error(line_number, "missing texture file".to_string()))?;
+				let texture_path: PathBuf = path.parent().unwrap_or(Path::new("")).join(file);
+				material.map_kd = match textures.get(&texture_path) {
+					Some(texture) => Some(texture.clone()),
+					None => match ImageTexture::load(&texture_path, ColorSpace::Srgb) {
+						Ok(texture) => {
+							let texture: Arc<ImageTexture> = Arc::new(texture);
+							textures.insert(texture_path, texture.clone());
+							Some(texture)
+						},
+						Err(err) => {
+							warnings.warn(line_number, format!("texture {} not loaded: {}", texture_path.display(), err));
+							None
+						}
+					}
+				};
+			},
+			// Ambient color has no meaning in a path tracer
+			"Ka" => {},
+			_ => warnings.unsupported(statement, line_number)
+		}
+	}
+
+	if let Some(finished) = current.take() {
+		add_material(&mut materials, &mut warnings, finished);
+	}
+
+	warnings.finish(all_warnings);
+	Ok(materials)
+}
+
+// Adds a material read up to its end, warning when its diffuse texture has no place
+// in the renderer material it becomes
+fn add_material(materials: &mut HashMap<String, MtlMaterial>, warnings: &mut Warnings, (name, line, material): (String, usize, MtlMaterial)) {
+	if material.map_kd.is_some() && (material.is_glass() || material.is_metal()) {
+		let kind: &str = if material.is_glass() { "glass" } else { "a metal" };
+		warnings.warn(line, format!("material '{}' becomes {}, its map_Kd is ignored", name, kind));
+	}
+	materials.insert(name, material);
+}
+
+// Mesh of a group. OBJ faces index positions, uvs and normals separately, so every
+// distinct combination becomes a vertex of the mesh. Normals and uvs are only kept
+// when all the vertices of the group have them
+fn build_mesh(group: &GroupBuilder, positions: &[Point3], uvs: &[Uv], normals: &[Vec3], material: Arc<dyn Material>) -> Result<TriangleMesh, String> {
+	let mut vertex_indices: HashMap<FaceVertex, u32> = HashMap::new();
+	let mut vertices: Vec<FaceVertex> = Vec::new();
+	let mut indices: Vec<[u32; 3]> = Vec::with_capacity(group.triangles.len());
+
+	for triangle in group.triangles.iter() {
+		indices.push(triangle.map(|vertex| {
+			*vertex_indices.entry(vertex).or_insert_with(|| {
+				vertices.push(vertex);
+				(vertices.len() - 1) as u32
+			})
+		}));
+	}
+
+	let mut mesh: TriangleMesh = TriangleMesh::new(
+		vertices.iter().map(|(position, _, _)| positions[*position]).collect(),
+		indices,
+		material
+	)?;
+
+	if vertices.iter().all(|(_, _, normal)| normal.is_some()) {
+		mesh = mesh.with_normals(vertices.iter().map(|(_, _, normal)| normals[normal.unwrap()]).collect())?;
+	}
+	if vertices.iter().all(|(_, uv, _)| uv.is_some()) {
+		mesh = mesh.with_uvs(vertices.iter().map(|(_, uv, _)| uvs[uv.unwrap()]).collect())?;
+	}
+	Ok(mesh)
+}
+
+fn parse_numbers(arguments: &[&str]) -> Result<Vec<f64>, String> {
+	arguments.iter()
+		.map(|argument| argument.parse::<f64>().map_err(|_| format!("'{}' is not a number", argument)))
+		.collect()
+}
+
+fn parse_vec3(arguments: &[&str]) -> Result<Vec3, String> {
+	match parse_numbers(arguments)?[..] {
+		// Extra values, like the w of positions or vertex colors, are ignored
+		[x, y, z, ..] => Ok(Vec3::new(x, y, z)),
+		_ => Err(format!("expected three numbers, found {}", arguments.len()))
+	}
+}
+
+fn parse_color(arguments: &[&str]) -> Result<Color, String> {
+	match parse_numbers(arguments)?[..] {
+		[gray] => Ok(Color::new(gray, gray, gray)),
+		[r, g, b] => Ok(Color::new(r, g, b)),
+		_ => Err(format!("expected one or three color values, found {}", arguments.len()))
+	}
+}
+
+// Face vertex as v, v/vt, v//vn or v/vt/vn. Indices start at 1, negative ones count
+// back from the last element read
+fn parse_face_vertex(vertex: &str, position_count: usize, uv_count: usize, normal_count: usize) -> Result<FaceVertex, String> {
+	let mut parts = vertex.split('/');
+	let position: usize = resolve_index(parts.next().unwrap_or(""), position_count, "vertex")?
+		.ok_or_else(|| format!("face vertex '{}' without position", vertex))?;
+	let uv: Option<usize> = resolve_index(parts.next().unwrap_or(""), uv_count, "texture coordinate")?;
+	let normal: Option<usize> = resolve_index(parts.next().unwrap_or(""), normal_count, "normal")?;
+	Ok((position, uv, normal))
+}
+
+fn resolve_index(index: &str, count: usize, kind: &str) -> Result<Option<usize>, String> {
+	if index.is_empty() {
+		return Ok(None);
+	}
+
+	let index: i64 = index.parse::<i64>().map_err(|_| format!("invalid {} index '{}'", kind, index))?;
+	let resolved: i64 = if index < 0 { count as i64 + index } else { index - 1 };
+	if resolved < 0 || resolved >= count as i64 {
+		return Err(format!("{} index {} out of range, {} defined", kind, index, count));
+	}
+	Ok(Some(resolved as usize))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn only_transparent_materials_become_glass() {
+		let material = |d: f64, illum: u32| MtlMaterial { d, illum, ..MtlMaterial::default() };
+		assert!(!material(0.99, 2).is_glass());
+		assert!(!material(0.5, 2).is_glass());
+		assert!(material(0.3, 2).is_glass());
+		assert!(material(1.0, 7).is_glass());
+	}
+
+	#[test]
+	fn lost_diffuse_texture_is_reported() {
+		let texture: PathBuf = Path::new(env!("CARGO_MANIFEST_DIR")).join("scenes/textures/checker.png");
+		let path: PathBuf = std::env::temp_dir().join(format!("ozopathtracer_lost_texture_{}.mtl", std::process::id()));
+		fs::write(&path, format!(
+			"newmtl painted\nKd 0.8 0.2 0.2\nd 0.99\nmap_Kd {0}\n\nnewmtl chrome\nKd 0.1 0.1 0.1\nKs 0.9 0.9 0.9\nmap_Kd {0}\n",
+			texture.display()
+		)).expect("temporary MTL file");
+
+		let mut warnings: Vec<String> = Vec::new();
+		let materials = load_mtl(&path, &mut warnings);
+		fs::remove_file(&path).ok();
+
+		let materials: HashMap<String, MtlMaterial> = materials.expect("MTL file");
+		assert!(!materials["painted"].is_glass());
+		assert_eq!(warnings.len(), 1, "{:?}", warnings);
+		assert!(warnings[0].contains("'chrome' becomes a metal"), "{}", warnings[0]);
+	}
+}
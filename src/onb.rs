@@ -0,0 +1,29 @@
+use crate::vec::{Vec3, cross, dot};
+
+/// Orthonormal basis built around a given `w` axis
+#[derive(Clone, Copy)]
+pub struct Onb {
+	pub u: Vec3,
+	pub v: Vec3,
+	pub w: Vec3,
+}
+
+impl Onb {
+	pub fn new(w: Vec3) -> Onb {
+		let w: Vec3 = w.unit_vector();
+		let a: Vec3 = if w.x.abs() > 0.9 { Vec3::new(0.0, 1.0, 0.0) } else { Vec3::new(1.0, 0.0, 0.0) };
+		let v: Vec3 = cross(w, a).unit_vector();
+		let u: Vec3 = cross(w, v);
+		Onb { u, v, w }
+	}
+
+	/// Vector given in basis coordinates, expressed in world coordinates
+	pub fn local(&self, a: Vec3) -> Vec3 {
+		a.x * self.u + a.y * self.v + a.z * self.w
+	}
+
+	/// Vector given in world coordinates, expressed in basis coordinates
+	pub fn to_local(&self, a: Vec3) -> Vec3 {
+		Vec3::new(dot(a, self.u), dot(a, self.v), dot(a, self.w))
+	}
+}
@@ -0,0 +1,164 @@
+use std::{
+	fmt,
+	fs::File,
+	io::{BufWriter, Write},
+	path::Path
+};
+
+use half::f16;
+use image::{codecs::hdr::HdrEncoder, ImageBuffer, Rgb, Rgb32FImage, RgbImage};
+
+use crate::framebuffer::Framebuffer;
+
+/// Gamma used by the 8-bit output unless told otherwise
+pub const DEFAULT_GAMMA: f32 = 2.0;
+
+/// Precision of the channels of OpenEXR files
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ExrPrecision {
+	Half,
+	Full,
+}
+
+/// Image file formats the renderer can write. Every format but PNG keeps the
+/// linear floating-point radiance.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum OutputFormat {
+	Png,
+	Exr(ExrPrecision),
+	Hdr,
+	Pfm,
+}
+
+impl OutputFormat {
+	/// Format matching the extension of the path. OpenEXR files default to full float
+	pub fn from_path(path: &Path) -> Option<OutputFormat> {
+		let extension: String = path.extension()?.to_str()?.to_ascii_lowercase();
+		match extension.as_str() {
+			"png" => Some(OutputFormat::Png),
+			"exr" => Some(OutputFormat::Exr(ExrPrecision::Full)),
+			"hdr" => Some(OutputFormat::Hdr),
+			"pfm" => Some(OutputFormat::Pfm),
+			_ => None
+		}
+	}
+
+	pub fn is_hdr(&self) -> bool {
+		*self != OutputFormat::Png
+	}
+}
+
+#[derive(Debug)]
+pub enum OutputError {
+	Io(std::io::Error),
+	Image(image::ImageError),
+	Exr(exr::error::Error),
+}
+
+impl fmt::Display for OutputError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			OutputError::Io(err) => write!(f, "{}", err),
+			OutputError::Image(err) => write!(f, "{}", err),
+			OutputError::Exr(err) => write!(f, "{}", err)
+		}
+	}
+}
+
+impl std::error::Error for OutputError {}
+
+impl From<std::io::Error> for OutputError {
+	fn from(err: std::io::Error) -> Self {
+		OutputError::Io(err)
+	}
+}
+
+impl From<image::ImageError> for OutputError {
+	fn from(err: image::ImageError) -> Self {
+		OutputError::Image(err)
+	}
+}
+
+impl From<exr::error::Error> for OutputError {
+	fn from(err: exr::error::Error) -> Self {
+		OutputError::Exr(err)
+	}
+}
+
+/// Gamma-encodes a linear image in place
+pub fn apply_gamma(image: &mut Rgb32FImage, gamma: f32) {
+	let exponent: f32 = 1.0 / gamma;
+	for channel in image.iter_mut() {
+		*channel = channel.max(0.0).powf(exponent);
+	}
+}
+
+/// Clamps every channel to [0, 1] and quantizes it to 8 bits
+pub fn quantize(image: &Rgb32FImage) -> RgbImage {
+	ImageBuffer::from_fn(image.width(), image.height(), |x, y| {
+		let pixel: &Rgb<f32> = image.get_pixel(x, y);
+		Rgb(pixel.0.map(|channel| (channel.clamp(0.0, 0.999) * 256.0) as u8))
+	})
+}
+
+/// Resolves the framebuffer and writes it in the given format. Floating-point
+/// formats are written linear, PNG is gamma-encoded and quantized first
+pub fn save(framebuffer: &Framebuffer, path: &Path, format: OutputFormat) -> Result<(), OutputError> {
+	let mut image: Rgb32FImage = framebuffer.resolve();
+
+	match format {
+		OutputFormat::Png => {
+			apply_gamma(&mut image, DEFAULT_GAMMA);
+			quantize(&image).save(path)?;
+			Ok(())
+		},
+		OutputFormat::Exr(precision) => write_exr(&image, path, precision),
+		OutputFormat::Hdr => write_hdr(&image, path),
+		OutputFormat::Pfm => write_pfm(&image, path)
+	}
+}
+
+/// Writes an OpenEXR file with RGB channels in half or full float
+pub fn write_exr(image: &Rgb32FImage, path: &Path, precision: ExrPrecision) -> Result<(), OutputError> {
+	let (width, height) = (image.width() as usize, image.height() as usize);
+	let channel = |x: usize, y: usize, c: usize| image.get_pixel(x as u32, y as u32)[c];
+
+	match precision {
+		ExrPrecision::Half => exr::prelude::write_rgb_file(path, width, height, |x, y| (
+			f16::from_f32(channel(x, y, 0)),
+			f16::from_f32(channel(x, y, 1)),
+			f16::from_f32(channel(x, y, 2))
+		))?,
+		ExrPrecision::Full => exr::prelude::write_rgb_file(path, width, height, |x, y| (
+			channel(x, y, 0),
+			channel(x, y, 1),
+			channel(x, y, 2)
+		))?
+	};
+	Ok(())
+}
+
+/// Writes a Radiance RGBE (.hdr) file
+pub fn write_hdr(image: &Rgb32FImage, path: &Path) -> Result<(), OutputError> {
+	let pixels: Vec<Rgb<f32>> = image.pixels().copied().collect();
+	let writer: BufWriter<File> = BufWriter::new(File::create(path)?);
+	HdrEncoder::new(writer).encode(&pixels, image.width() as usize, image.height() as usize)?;
+	Ok(())
+}
+
+/// Writes a Portable Float Map. Rows are stored bottom to top, in little endian
+pub fn write_pfm(image: &Rgb32FImage, path: &Path) -> Result<(), OutputError> {
+	let mut writer: BufWriter<File> = BufWriter::new(File::create(path)?);
+
+	// A negative scale marks little endian samples
+	write!(writer, "PF\n{} {}\n-1.0\n", image.width(), image.height())?;
+	for y in (0..image.height()).rev() {
+		for x in 0..image.width() {
+			for channel in image.get_pixel(x, y).0 {
+				writer.write_all(&channel.to_le_bytes())?;
+			}
+		}
+	}
+	writer.flush()?;
+	Ok(())
+}
@@ -0,0 +1,307 @@
+use std::{
+	fmt,
+	fs,
+	path::{Path, PathBuf},
+	sync::Arc
+};
+
+use crate::{
+	color::Color,
+	material::Material,
+	mesh::TriangleMesh,
+	triangle::Uv,
+	vec::{Point3, Vec3}
+};
+
+#[derive(Debug)]
+pub enum PlyError {
+	Io(PathBuf, std::io::Error),
+	Parse { path: PathBuf, message: String },
+}
+
+impl fmt::Display for PlyError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			PlyError::Io(path, err) => write!(f, "{}: {}", path.display(), err),
+			PlyError::Parse { path, message } => write!(f, "{}: {}", path.display(), message)
+		}
+	}
+}
+
+impl std::error::Error for PlyError {}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Encoding {
+	Ascii,
+	BinaryLittleEndian,
+	BinaryBigEndian,
+}
+
+#[derive(Clone, Copy)]
+enum ScalarType {
+	I8, U8, I16, U16, I32, U32, F32, F64,
+}
+
+impl ScalarType {
+	fn parse(name: &str) -> Option<ScalarType> {
+		match name {
+			"char" | "int8" => Some(ScalarType::I8),
+			"uchar" | "uint8" => Some(ScalarType::U8),
+			"short" | "int16" => Some(ScalarType::I16),
+			"ushort" | "uint16" => Some(ScalarType::U16),
+			"int" | "int32" => Some(ScalarType::I32),
+			"uint" | "uint32" => Some(ScalarType::U32),
+			"float" | "float32" => Some(ScalarType::F32),
+			"double" | "float64" => Some(ScalarType::F64),
+			_ => None
+		}
+	}
+
+	fn size(self) -> usize {
+		match self {
+			ScalarType::I8 | ScalarType::U8 => 1,
+			ScalarType::I16 | ScalarType::U16 => 2,
+			ScalarType::I32 | ScalarType::U32 | ScalarType::F32 => 4,
+			ScalarType::F64 => 8
+		}
+	}
+
+	// Largest value of integer color channels, which map to [0, 1]
+	fn color_scale(self) -> f64 {
+		match self {
+			ScalarType::U8 => 255.0,
+			ScalarType::U16 => 65535.0,
+			_ => 1.0
+		}
+	}
+}
+
+enum PropertyType {
+	Scalar(ScalarType),
+	List { count: ScalarType, item: ScalarType },
+}
+
+struct Property {
+	name: String,
+	kind: PropertyType,
+}
+
+struct Element {
+	name: String,
+	count: usize,
+	properties: Vec<Property>,
+}
+
+impl Element {
+	// Indices of the properties named after each group of aliases, when all are present
+	fn find<const N: usize>(&self, names: [&[&str]; N]) -> Option<[usize; N]> {
+		let mut found: [usize; N] = [0; N];
+		for (index, aliases) in found.iter_mut().zip(names) {
+			*index = self.properties.iter().position(|property| aliases.contains(&property.name.as_str()))?;
+		}
+		Some(found)
+	}
+}
+
+// Sequential reader of the values of the body, in any of the encodings
+struct BodyReader<'a> {
+	encoding: Encoding,
+	body: &'a [u8],
+	position: usize,
+	tokens: std::str::SplitAsciiWhitespace<'a>,
+}
+
+impl<'a> BodyReader<'a> {
+	fn new(encoding: Encoding, body: &'a [u8]) -> Result<BodyReader<'a>, String> {
+		let text: &str = match encoding {
+			Encoding::Ascii => std::str::from_utf8(body).map_err(|_| "ASCII body is not valid text".to_string())?,
+			_ => ""
+		};
+		Ok(BodyReader { encoding, body, position: 0, tokens: text.split_ascii_whitespace() })
+	}
+
+	fn read(&mut self, kind: ScalarType) -> Result<f64, String> {
+		if self.encoding == Encoding::Ascii {
+			let token: &str = self.tokens.next().ok_or_else(|| "unexpected end of data".to_string())?;
+			return token.parse::<f64>().map_err(|_| format!("'{}' is not a number", token));
+		}
+
+		let bytes: &[u8] = self.body.get(self.position..self.position + kind.size())
+			.ok_or_else(|| "unexpected end of data".to_string())?;
+		self.position += kind.size();
+
+		macro_rules! decode {
+			($type:ty) => {{
+				let bytes: [u8; std::mem::size_of::<$type>()] = bytes.try_into().unwrap();
+				if self.encoding == Encoding::BinaryLittleEndian {
+					<$type>::from_le_bytes(bytes) as f64
+				} else {
+					<$type>::from_be_bytes(bytes) as f64
+				}
+			}};
+		}
+
+		Ok(match kind {
+			ScalarType::I8 => decode!(i8),
+			ScalarType::U8 => decode!(u8),
+			ScalarType::I16 => decode!(i16),
+			ScalarType::U16 => decode!(u16),
+			ScalarType::I32 => decode!(i32),
+			ScalarType::U32 => decode!(u32),
+			ScalarType::F32 => decode!(f32),
+			ScalarType::F64 => decode!(f64)
+		})
+	}
+}
+
+/// Triangle mesh of a PLY file, ASCII or binary of either endianness. Vertex
+/// normals, texture coordinates and colors are kept when present, the colors
+/// scaling the albedo of the material. Polygons are split in triangle fans
+pub fn load_ply(path: &Path, material: Arc<dyn Material>) -> Result<TriangleMesh, PlyError> {
+	let bytes: Vec<u8> = fs::read(path).map_err(|err| PlyError::Io(path.to_path_buf(), err))?;
+	parse_ply(&bytes, material).map_err(|message| PlyError::Parse { path: path.to_path_buf(), message })
+}
+
+fn parse_ply(bytes: &[u8], material: Arc<dyn Material>) -> Result<TriangleMesh, String> {
+	const END_HEADER: &[u8] = b"end_header";
+	let header_end: usize = bytes.windows(END_HEADER.len())
+		.position(|window| window == END_HEADER)
+		.ok_or_else(|| "missing end_header".to_string())?;
+	let body_start: usize = bytes[header_end..].iter()
+		.position(|byte| *byte == b'\n')
+		.map_or(bytes.len(), |i| header_end + i + 1);
+
+	let header: &str = std::str::from_utf8(&bytes[..header_end]).map_err(|_| "header is not valid text".to_string())?;
+	let (encoding, elements) = parse_header(header)?;
+
+	let mut reader: BodyReader = BodyReader::new(encoding, &bytes[body_start..])?;
+	let mut positions: Vec<Point3> = Vec::new();
+	let mut normals: Vec<Vec3> = Vec::new();
+	let mut uvs: Vec<Uv> = Vec::new();
+	let mut colors: Vec<Color> = Vec::new();
+	let mut indices: Vec<[u32; 3]> = Vec::new();
+
+	for element in elements.iter() {
+		let position_properties: Option<[usize; 3]> = element.find([&["x"], &["y"], &["z"]]);
+		let normal_properties: Option<[usize; 3]> = element.find([&["nx"], &["ny"], &["nz"]]);
+		let uv_properties: Option<[usize; 2]> = element.find([&["u", "s", "texture_u", "texture_s"], &["v", "t", "texture_v", "texture_t"]]);
+		let color_properties: Option<[usize; 3]> = element.find([&["red", "r"], &["green", "g"], &["blue", "b"]]);
+		let index_property: Option<usize> = element.find([&["vertex_indices", "vertex_index"]]).map(|[i]| i);
+
+		let mut scalars: Vec<f64> = vec![0.0; element.properties.len()];
+		let mut list: Vec<f64> = Vec::new();
+		for _ in 0..element.count {
+			for (i, property) in element.properties.iter().enumerate() {
+				match property.kind {
+					PropertyType::Scalar(kind) => scalars[i] = reader.read(kind)?,
+					PropertyType::List { count, item } => {
+						let count: usize = reader.read(count)? as usize;
+						// Only the face indices are kept, other lists are just skipped
+						let keep: bool = element.name == "face" && Some(i) == index_property;
+						if keep {
+							list.clear();
+						}
+						for _ in 0..count {
+							let value: f64 = reader.read(item)?;
+							if keep {
+								list.push(value);
+							}
+						}
+					}
+				}
+			}
+
+			match element.name.as_str() {
+				"vertex" => {
+					let [x, y, z] = position_properties.ok_or_else(|| "vertex element without x, y and z".to_string())?;
+					positions.push(Point3::new(scalars[x], scalars[y], scalars[z]));
+					if let Some([nx, ny, nz]) = normal_properties {
+						normals.push(Vec3::new(scalars[nx], scalars[ny], scalars[nz]));
+					}
+					if let Some([u, v]) = uv_properties {
+						uvs.push([scalars[u], scalars[v]]);
+					}
+					if let Some([r, g, b]) = color_properties {
+						let scale = |i: usize| match element.properties[i].kind {
+							PropertyType::Scalar(kind) => kind.color_scale(),
+							PropertyType::List { .. } => 1.0
+						};
+						colors.push(Color::new(scalars[r] / scale(r), scalars[g] / scale(g), scalars[b] / scale(b)));
+					}
+				},
+				"face" => {
+					if index_property.is_none() {
+						return Err("face element without vertex_indices".to_string());
+					}
+					for i in 1..list.len().saturating_sub(1) {
+						indices.push([list[0] as u32, list[i] as u32, list[i + 1] as u32]);
+					}
+				},
+				_ => {}
+			}
+		}
+	}
+
+	let mut mesh: TriangleMesh = TriangleMesh::new(positions, indices, material)?;
+	if !normals.is_empty() {
+		mesh = mesh.with_normals(normals)?;
+	}
+	if !uvs.is_empty() {
+		mesh = mesh.with_uvs(uvs)?;
+	}
+	if !colors.is_empty() {
+		mesh = mesh.with_colors(colors)?;
+	}
+	Ok(mesh)
+}
+
+fn parse_header(header: &str) -> Result<(Encoding, Vec<Element>), String> {
+	let mut lines = header.lines().map(str::trim);
+	if lines.next() != Some("ply") {
+		return Err("not a PLY file".to_string());
+	}
+
+	let mut encoding: Option<Encoding> = None;
+	let mut elements: Vec<Element> = Vec::new();
+	for line in lines {
+		let tokens: Vec<&str> = line.split_whitespace().collect();
+		match tokens[..] {
+			[] | ["comment", ..] | ["obj_info", ..] => {},
+			["format", format, _version] => {
+				encoding = Some(match format {
+					"ascii" => Encoding::Ascii,
+					"binary_little_endian" => Encoding::BinaryLittleEndian,
+					"binary_big_endian" => Encoding::BinaryBigEndian,
+					_ => return Err(format!("unknown format '{}'", format))
+				});
+			},
+			["element", name, count] => elements.push(Element {
+				name: name.to_string(),
+				count: count.parse::<usize>().map_err(|_| format!("invalid element count '{}'", count))?,
+				properties: Vec::new()
+			}),
+			["property", "list", count, item, name] => {
+				let kind: PropertyType = PropertyType::List {
+					count: scalar_type(count)?,
+					item: scalar_type(item)?
+				};
+				add_property(&mut elements, name, kind)?;
+			},
+			["property", kind, name] => add_property(&mut elements, name, PropertyType::Scalar(scalar_type(kind)?))?,
+			_ => return Err(format!("invalid header line '{}'", line))
+		}
+	}
+
+	let encoding: Encoding = encoding.ok_or_else(|| "missing format".to_string())?;
+	Ok((encoding, elements))
+}
+
+fn scalar_type(name: &str) -> Result<ScalarType, String> {
+	ScalarType::parse(name).ok_or_else(|| format!("unknown property type '{}'", name))
+}
+
+fn add_property(elements: &mut [Element], name: &str, kind: PropertyType) -> Result<(), String> {
+	let element: &mut Element = elements.last_mut().ok_or_else(|| format!("property '{}' before any element", name))?;
+	element.properties.push(Property { name: name.to_string(), kind });
+	Ok(())
+}
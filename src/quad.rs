@@ -0,0 +1,131 @@
+use std::sync::Arc;
+
+use crate::{
+	aabb::Aabb,
+	color::WHITE,
+	hit::{HitRecord, Hitable},
+	light::{Light, LightSample, same_distance},
+	material::Material,
+	sampler::Sampler,
+	ray::Ray,
+	vec::{Point3, Vec3, cross, dot}
+};
+
+/// Parallelogram with a corner at `q` and sides `u` and `v`
+pub struct Quad {
+	q: Point3,
+	u: Vec3,
+	v: Vec3,
+	w: Vec3,		// Maps points of the plane to their (alpha, beta) coordinates
+	normal: Vec3,
+	d: f64,			// Plane equation: dot(normal, p) = d
+	area: f64,
+	material: Arc<dyn Material>,
+}
+
+impl Quad {
+	pub fn new(q: Point3, u: Vec3, v: Vec3, material: Arc<dyn Material>) -> Quad {
+		let n: Vec3 = cross(u, v);
+		let normal: Vec3 = n.unit_vector();
+
+		Quad {
+			q,
+			u,
+			v,
+			w: n / dot(n, n),
+			normal,
+			d: dot(normal, q),
+			area: n.length(),
+			material
+		}
+	}
+}
+
+impl Quad {
+	// Closest hit of the surface, which the light sampling also needs without a sampler
+	fn intersect(&self, ray: &Ray, ray_tmin: f64, ray_tmax: f64) -> Option<HitRecord> {
+		let denom: f64 = dot(self.normal, ray.dir());
+
+		// No hit if the ray is parallel to the plane
+		if denom.abs() < 1e-8 {
+			return None;
+		}
+
+		let t: f64 = (self.d - dot(self.normal, ray.origin())) / denom;
+		if t < ray_tmin || t > ray_tmax {
+			return None;
+		}
+
+		// Check the hit point of the plane lies inside the parallelogram
+		let hit_point: Point3 = ray.at(t);
+		let planar_hit: Vec3 = hit_point - self.q;
+		let alpha: f64 = dot(self.w, cross(planar_hit, self.v));
+		let beta: f64 = dot(self.w, cross(self.u, planar_hit));
+		if !(0.0..=1.0).contains(&alpha) || !(0.0..=1.0).contains(&beta) {
+			return None;
+		}
+
+		let front_face: bool = denom < 0.0;
+		Some(HitRecord {
+			p: hit_point,
+			normal: if front_face { self.normal } else { -self.normal },
+			t,
+			u: alpha,
+			v: beta,
+			dpdu: self.u,
+			dpdv: self.v,
+			uv_footprint: 0.0,
+			front_face,
+			color: WHITE,
+			material: self.material.clone()
+		})
+	}
+}
+
+impl Hitable for Quad {
+	fn hit(&self, ray: &Ray, ray_tmin: f64, ray_tmax: f64, _sampler: &mut dyn Sampler) -> Option<HitRecord> {
+		self.intersect(ray, ray_tmin, ray_tmax)
+	}
+
+	fn bounding_box(&self) -> Aabb {
+		// Padded so the box of an axis aligned quad is never flat
+		const PADDING: f64 = 1e-4;
+		let bbox: Aabb = Aabb::new(self.q, self.q + self.u + self.v)
+			.include_point(self.q + self.u)
+			.include_point(self.q + self.v);
+		let padding: Vec3 = Vec3::new(PADDING, PADDING, PADDING);
+		Aabb::new(bbox.min - padding, bbox.max + padding)
+	}
+}
+
+impl Light for Quad {
+	fn sample(&self, origin: Point3, sampler: &mut dyn Sampler) -> Option<LightSample> {
+		// Uniform point of the area, converted to a density in solid angle
+		let (a, b) = sampler.get_2d();
+		let point: Point3 = self.q + a * self.u + b * self.v;
+		let to_point: Vec3 = point - origin;
+		let distance: f64 = to_point.length();
+		let direction: Vec3 = to_point / distance;
+
+		let cosine: f64 = dot(direction, self.normal).abs();
+		if cosine < 1e-8 {
+			return None;
+		}
+
+		Some(LightSample {
+			direction,
+			distance,
+			pdf: distance * distance / (cosine * self.area)
+		})
+	}
+
+	fn pdf(&self, origin: Point3, direction: Vec3, distance: f64) -> f64 {
+		match self.intersect(&Ray::new(origin, direction), 0.001, f64::INFINITY) {
+			Some(hit) if same_distance(hit.t, distance) => {
+				let cosine: f64 = dot(direction, self.normal).abs();
+				hit.t * hit.t / (cosine * self.area)
+			},
+			_ => 0.0
+		}
+	}
+}
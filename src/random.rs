@@ -1,11 +1,41 @@
-use fastrand;
+/// Random number generator passed explicitly to everything that samples. Each
+/// sample of each pixel gets its own, so a seed always renders the same image
+/// whatever order the threads take the pixels in
+pub struct Rng {
+	state: fastrand::Rng,
+}
+
+impl Rng {
+	pub fn new(seed: u64) -> Rng {
+		Rng { state: fastrand::Rng::with_seed(seed) }
+	}
+
+	/// Generator of one sample of a pixel, seeded from the render seed and the
+	/// sample coordinates. Neighbouring samples get unrelated sequences
+	pub fn for_sample(seed: u64, x: u32, y: u32, sample: u32) -> Rng {
+		let key: u64 = mix(mix(mix(seed) ^ x as u64) ^ ((y as u64) << 32 | sample as u64));
+		Rng::new(key)
+	}
 
-#[inline]
-pub fn random_f64() -> f64 {
-	fastrand::f64()	
+	#[inline]
+	pub fn f64(&mut self) -> f64 {
+		self.state.f64()
+	}
+
+	#[inline]
+	pub fn f64_range(&mut self, min: f64, max: f64) -> f64 {
+		min + self.state.f64() * (max - min)
+	}
+
+	pub fn shuffle<T>(&mut self, slice: &mut [T]) {
+		self.state.shuffle(slice)
+	}
 }
 
-#[inline]
-pub fn random_f64_range(min: f64, max: f64) -> f64 {
-	min + fastrand::f64() * (max - min)
-}
\ No newline at end of file
+// Finalizer of SplitMix64, every input bit affects every output bit
+pub(crate) fn mix(value: u64) -> u64 {
+	let mut z: u64 = value.wrapping_add(0x9E37_79B9_7F4A_7C15);
+	z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+	z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+	z ^ (z >> 31)
+}
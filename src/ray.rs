@@ -1,19 +1,36 @@
 use super::vec::{Point3, Vec3};
 
+/// Rays through the neighbouring pixels, offset by one pixel along X and along Y.
+/// They tell how large the footprint of a pixel is where the main ray hits
+#[derive(Clone, Copy)]
+pub struct RayDifferentials {
+	pub x_origin: Point3,
+	pub x_dir: Vec3,
+	pub y_origin: Point3,
+	pub y_dir: Vec3,
+}
+
 #[derive(Clone, Copy)]
 pub struct Ray {
 	origin: Point3,
 	dir: Vec3,
+	differentials: Option<RayDifferentials>,	// Only camera rays have them
 }
 
 impl Ray {
 	pub fn new(origin: Point3, dir: Vec3) -> Ray {
 		Ray {
-			origin: origin,
-			dir: dir
+			origin,
+			dir,
+			differentials: None
 		}
 	}
 
+	pub fn with_differentials(mut self, differentials: RayDifferentials) -> Ray {
+		self.differentials = Some(differentials);
+		self
+	}
+
 	pub fn origin(&self) -> Point3 {
 		self.origin
 	}
@@ -22,7 +39,11 @@ impl Ray {
 		self.dir
 	}
 
+	pub fn differentials(&self) -> Option<&RayDifferentials> {
+		self.differentials.as_ref()
+	}
+
 	pub fn at(&self, t: f64) -> Point3 {
 		self.origin + (t * self.dir)
 	}
-}
\ No newline at end of file
+}
@@ -0,0 +1,348 @@
+use std::{fmt, str::FromStr};
+
+use serde::Deserialize;
+
+use crate::random::{Rng, mix};
+
+/// Source of the random numbers of a path. Each call returns the next dimension of
+/// the current sample: the pixel position, the lens position, then the dimensions
+/// consumed by every bounce. Samplers other than the independent one spread the
+/// samples of a pixel evenly over every dimension, which lowers the noise
+pub trait Sampler: Send {
+	/// Restarts the dimensions for sample `index` of the pixel (x, y)
+	fn start_sample(&mut self, x: u32, y: u32, index: u32);
+
+	fn get_1d(&mut self) -> f64;
+
+	fn get_2d(&mut self) -> (f64, f64);
+}
+
+/// Sampler picked in the camera settings. Every kind other than `Independent`
+/// spreads the samples of a pixel evenly, so it usually renders less noise at
+/// the same sample count
+#[derive(Clone, Copy, Default, PartialEq, Debug, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SamplerKind {
+	/// Uniform random numbers, every sample on its own
+	#[default]
+	Independent,
+	/// One jittered sample in each stratum of the pixel samples
+	Stratified,
+	/// Halton points, randomly shifted in every pixel
+	Halton,
+	/// Sobol points with Owen scrambling
+	Sobol,
+}
+
+impl SamplerKind {
+	/// Sampler drawing `samples_per_pixel` samples in every pixel. The same seed
+	/// always gives the same numbers
+	pub fn build(self, seed: u64, samples_per_pixel: u32) -> Box<dyn Sampler> {
+		let state: SampleState = SampleState::new(seed);
+		match self {
+			SamplerKind::Independent => Box::new(IndependentSampler { state }),
+			SamplerKind::Stratified => Box::new(StratifiedSampler { state, samples_per_pixel }),
+			SamplerKind::Halton => Box::new(HaltonSampler { state, samples_per_pixel }),
+			SamplerKind::Sobol => Box::new(SobolSampler { state })
+		}
+	}
+}
+
+impl fmt::Display for SamplerKind {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		let name: &str = match self {
+			SamplerKind::Independent => "independent",
+			SamplerKind::Stratified => "stratified",
+			SamplerKind::Halton => "halton",
+			SamplerKind::Sobol => "sobol"
+		};
+		write!(f, "{}", name)
+	}
+}
+
+impl FromStr for SamplerKind {
+	type Err = String;
+
+	fn from_str(name: &str) -> Result<SamplerKind, String> {
+		match name {
+			"independent" => Ok(SamplerKind::Independent),
+			"stratified" => Ok(SamplerKind::Stratified),
+			"halton" => Ok(SamplerKind::Halton),
+			"sobol" => Ok(SamplerKind::Sobol),
+			_ => Err(format!("unknown sampler '{}', use independent, stratified, halton or sobol", name))
+		}
+	}
+}
+
+// Current sample of every sampler. Each dimension of a pixel gets its own seed,
+// so the dimensions are shuffled independently of each other
+struct SampleState {
+	seed: u64,
+	pixel_seed: u64,
+	index: u32,
+	dimension: u64,
+	rng: Rng,			// Random numbers of the sample, for jittering
+}
+
+impl SampleState {
+	fn new(seed: u64) -> SampleState {
+		SampleState { seed, pixel_seed: seed, index: 0, dimension: 0, rng: Rng::new(seed) }
+	}
+
+	fn start(&mut self, x: u32, y: u32, index: u32) {
+		self.pixel_seed = mix(mix(self.seed ^ x as u64) ^ y as u64);
+		self.index = index;
+		self.dimension = 0;
+		self.rng = Rng::for_sample(self.seed, x, y, index);
+	}
+
+	// Seed of the next dimension of the pixel
+	fn next_dimension(&mut self) -> u32 {
+		self.dimension += 1;
+		mix(self.pixel_seed ^ self.dimension) as u32
+	}
+}
+
+/// Independent uniform random numbers
+pub struct IndependentSampler {
+	state: SampleState,
+}
+
+impl Sampler for IndependentSampler {
+	fn start_sample(&mut self, x: u32, y: u32, index: u32) {
+		self.state.start(x, y, index);
+	}
+
+	fn get_1d(&mut self) -> f64 {
+		self.state.rng.f64()
+	}
+
+	fn get_2d(&mut self) -> (f64, f64) {
+		(self.state.rng.f64(), self.state.rng.f64())
+	}
+}
+
+/// Jittered stratification. In one dimension the unit interval is split in one
+/// stratum per sample, in two dimensions the square is split in a grid as large
+/// as the sample count allows. The strata are shuffled in every dimension
+pub struct StratifiedSampler {
+	state: SampleState,
+	samples_per_pixel: u32,
+}
+
+impl Sampler for StratifiedSampler {
+	fn start_sample(&mut self, x: u32, y: u32, index: u32) {
+		self.state.start(x, y, index);
+	}
+
+	fn get_1d(&mut self) -> f64 {
+		let stratum: u32 = permute(self.state.index, self.samples_per_pixel, self.state.next_dimension());
+		(stratum as f64 + self.state.rng.f64()) / self.samples_per_pixel as f64
+	}
+
+	fn get_2d(&mut self) -> (f64, f64) {
+		// Samples left over past the largest square grid are not stratified
+		let side: u32 = (self.samples_per_pixel as f64).sqrt() as u32;
+		let seed: u32 = self.state.next_dimension();
+		if self.state.index >= side * side {
+			return (self.state.rng.f64(), self.state.rng.f64());
+		}
+
+		let stratum: u32 = permute(self.state.index, side * side, seed);
+		(
+			((stratum % side) as f64 + self.state.rng.f64()) / side as f64,
+			((stratum / side) as f64 + self.state.rng.f64()) / side as f64
+		)
+	}
+}
+
+/// First points of the Halton sequence in bases 2 and 3, in a shuffled order in
+/// every dimension and shifted by a random offset in every pixel
+pub struct HaltonSampler {
+	state: SampleState,
+	samples_per_pixel: u32,
+}
+
+impl HaltonSampler {
+	fn point(&mut self) -> (u32, u32) {
+		let seed: u32 = self.state.next_dimension();
+		(permute(self.state.index, self.samples_per_pixel, seed), seed)
+	}
+}
+
+impl Sampler for HaltonSampler {
+	fn start_sample(&mut self, x: u32, y: u32, index: u32) {
+		self.state.start(x, y, index);
+	}
+
+	fn get_1d(&mut self) -> f64 {
+		let (point, seed) = self.point();
+		shift(radical_inverse(2, point), seed)
+	}
+
+	fn get_2d(&mut self) -> (f64, f64) {
+		let (point, seed) = self.point();
+		(shift(radical_inverse(2, point), seed), shift(radical_inverse(3, point), mix(seed as u64) as u32))
+	}
+}
+
+/// First two dimensions of the Sobol sequence with nested uniform Owen scrambling,
+/// their order shuffled in every dimension. This is the hash-based scrambling of
+/// Burley, "Practical Hash-based Owen Scrambling" (2020)
+pub struct SobolSampler {
+	state: SampleState,
+}
+
+impl SobolSampler {
+	fn point(&mut self) -> (u32, u32) {
+		let seed: u32 = self.state.next_dimension();
+		(nested_uniform_scramble(self.state.index, seed), seed)
+	}
+}
+
+impl Sampler for SobolSampler {
+	fn start_sample(&mut self, x: u32, y: u32, index: u32) {
+		self.state.start(x, y, index);
+	}
+
+	fn get_1d(&mut self) -> f64 {
+		let (point, seed) = self.point();
+		unit_float(nested_uniform_scramble(point.reverse_bits(), mix(seed as u64) as u32))
+	}
+
+	fn get_2d(&mut self) -> (f64, f64) {
+		let (point, seed) = self.point();
+		let x_seed: u32 = mix(seed as u64) as u32;
+		let y_seed: u32 = mix(x_seed as u64) as u32;
+		(
+			unit_float(nested_uniform_scramble(point.reverse_bits(), x_seed)),
+			unit_float(nested_uniform_scramble(sobol_second_dimension(point), y_seed))
+		)
+	}
+}
+
+// Pseudo-random permutation of `index` in [0, length), from Kensler, "Correlated
+// Multi-Jittered Sampling" (2013). Values outside the range are walked back into it
+fn permute(index: u32, length: u32, seed: u32) -> u32 {
+	let mut mask: u32 = length.saturating_sub(1);
+	mask |= mask >> 1;
+	mask |= mask >> 2;
+	mask |= mask >> 4;
+	mask |= mask >> 8;
+	mask |= mask >> 16;
+
+	let mut i: u32 = index;
+	loop {
+		i ^= seed;
+		i = i.wrapping_mul(0xe170893d);
+		i ^= seed >> 16;
+		i ^= (i & mask) >> 4;
+		i ^= seed >> 8;
+		i = i.wrapping_mul(0x0929eb3f);
+		i ^= seed >> 23;
+		i ^= (i & mask) >> 1;
+		i = i.wrapping_mul(1 | seed >> 27);
+		i = i.wrapping_mul(0x6935fa69);
+		i ^= (i & mask) >> 11;
+		i = i.wrapping_mul(0x74dcb303);
+		i ^= (i & mask) >> 2;
+		i = i.wrapping_mul(0x9e501cc3);
+		i ^= (i & mask) >> 2;
+		i = i.wrapping_mul(0xc860a3df);
+		i &= mask;
+		i ^= i >> 5;
+		if i < length {
+			break;
+		}
+	}
+	i.wrapping_add(seed) % length.max(1)
+}
+
+// Digits of `index` in `base` mirrored around the radix point
+fn radical_inverse(base: u32, index: u32) -> f64 {
+	let inverse_base: f64 = 1.0 / base as f64;
+	let mut index: u32 = index;
+	let mut reversed: f64 = 0.0;
+	let mut scale: f64 = inverse_base;
+	while index > 0 {
+		reversed += (index % base) as f64 * scale;
+		index /= base;
+		scale *= inverse_base;
+	}
+	reversed
+}
+
+// Cranley-Patterson rotation of a value by an offset derived from the seed
+fn shift(value: f64, seed: u32) -> f64 {
+	let shifted: f64 = value + unit_float(seed);
+	if shifted >= 1.0 { shifted - 1.0 } else { shifted }
+}
+
+// Sobol dimension of the primitive polynomial x + 1, each direction number the
+// previous one xored with itself shifted right
+fn sobol_second_dimension(index: u32) -> u32 {
+	let mut direction: u32 = 1 << 31;
+	let mut value: u32 = 0;
+	let mut index: u32 = index;
+	while index > 0 {
+		if index & 1 == 1 {
+			value ^= direction;
+		}
+		index >>= 1;
+		direction ^= direction >> 1;
+	}
+	value
+}
+
+// Owen scrambling of the bits of `value`, seen as a binary fraction
+fn nested_uniform_scramble(value: u32, seed: u32) -> u32 {
+	laine_karras_permutation(value.reverse_bits(), seed).reverse_bits()
+}
+
+// Hash in which every bit only depends on the bits below it
+fn laine_karras_permutation(value: u32, seed: u32) -> u32 {
+	let mut x: u32 = value.wrapping_add(seed);
+	x ^= x.wrapping_mul(0x6c50b47c);
+	x ^= x.wrapping_mul(0xb82f1e52);
+	x ^= x.wrapping_mul(0xc7afe638);
+	x ^= x.wrapping_mul(0x8d22f6e6);
+	x
+}
+
+fn unit_float(bits: u32) -> f64 {
+	bits as f64 / 4294967296.0
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	// Root mean square error of the area of the quarter disk, estimated in 64 pixels
+	fn quarter_disk_error(kind: SamplerKind) -> f64 {
+		let samples_per_pixel: u32 = 64;
+		let mut sampler: Box<dyn Sampler> = kind.build(7, samples_per_pixel);
+		let mut squared_error: f64 = 0.0;
+		for pixel in 0..64 {
+			let mut inside: u32 = 0;
+			for index in 0..samples_per_pixel {
+				sampler.start_sample(pixel, 0, index);
+				sampler.get_1d();
+				let (x, y) = sampler.get_2d();
+				if x * x + y * y < 1.0 {
+					inside += 1;
+				}
+			}
+			let estimate: f64 = inside as f64 / samples_per_pixel as f64;
+			squared_error += (estimate - std::f64::consts::FRAC_PI_4).powi(2);
+		}
+		(squared_error / 64.0).sqrt()
+	}
+
+	#[test]
+	fn samplers_integrate_better_than_independent() {
+		let independent: f64 = quarter_disk_error(SamplerKind::Independent);
+		for kind in [SamplerKind::Stratified, SamplerKind::Halton, SamplerKind::Sobol] {
+			assert!(quarter_disk_error(kind) < 0.5 * independent, "{} is not better than independent", kind);
+		}
+	}
+}
@@ -0,0 +1,985 @@
+use std::{
+	collections::{HashMap, HashSet, hash_map::Entry},
+	fmt,
+	fs,
+	path::{Path, PathBuf},
+	sync::Arc
+};
+
+use serde::Deserialize;
+use toml::Spanned;
+
+use crate::{
+	aabb::Aabb,
+	background::Background,
+	camera::CameraSettings,
+	color::{Color, BLACK, WHITE},
+	gltf::{GltfCamera, GltfModel, GltfError, load_gltf},
+	instance::Instance,
+	constant_medium::ConstantMedium,
+	grid_medium::GridMedium,
+	voxel_grid::{VoxelGrid, VoxelGridError, VoxelType, load_nrrd, load_raw},
+	hit::{Hitable, HitableList},
+	light::{Light, LightList},
+	mesh::TriangleMesh,
+	material::{Material, Lambertian, Metal, Conductor, ConductorPreset, Dielectric, DiffuseLight, Principled, Isotropic, HenyeyGreenstein},
+	obj::{ObjModel, ObjError, load_obj},
+	ply::{PlyError, load_ply},
+	stl::{StlError, load_stl},
+	texture::{Texture, SolidColor, CheckerTexture, ImageTexture, ColorSpace, WrapMode, FilterMode, NoiseTexture},
+	quad::Quad,
+	random::Rng,
+	sphere::Sphere,
+	triangle::Triangle,
+	vec::{Mat4, Point3, Vec3, cross}
+};
+
+/// Scene ready to be rendered: camera settings plus every object of the world.
+/// Objects with an emissive material are also part of the lights. Warnings tell
+/// what parts of the imported models were left out
+pub struct Scene {
+	pub camera: CameraSettings,
+	pub world: HitableList,
+	pub lights: LightList,
+	pub background: Background,
+	pub warnings: Vec<String>,
+}
+
+#[derive(Debug)]
+pub enum SceneError {
+	Io(std::io::Error),
+	Parse { line: usize, column: usize, message: String },
+	Obj(ObjError),
+	Gltf(GltfError),
+	Ply(PlyError),
+	Stl(StlError),
+	Volume(VoxelGridError),
+	UnsupportedModel(PathBuf),
+	Image(PathBuf, image::ImageError),
+}
+
+impl fmt::Display for SceneError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			SceneError::Io(err) => write!(f, "{}", err),
+			SceneError::Parse { line, column, message } =>
+				write!(f, "line {}, column {}: {}", line, column, message),
+			SceneError::Obj(err) => write!(f, "{}", err),
+			SceneError::Gltf(err) => write!(f, "{}", err),
+			SceneError::Ply(err) => write!(f, "{}", err),
+			SceneError::Stl(err) => write!(f, "{}", err),
+			SceneError::Volume(err) => write!(f, "{}", err),
+			SceneError::UnsupportedModel(path) =>
+				write!(f, "{}: unsupported model format, use obj, gltf, glb, ply or stl", path.display()),
+			SceneError::Image(path, err) => write!(f, "{}: {}", path.display(), err)
+		}
+	}
+}
+
+impl std::error::Error for SceneError {}
+
+impl From<std::io::Error> for SceneError {
+	fn from(err: std::io::Error) -> Self {
+		SceneError::Io(err)
+	}
+}
+
+impl From<ObjError> for SceneError {
+	fn from(err: ObjError) -> Self {
+		SceneError::Obj(err)
+	}
+}
+
+impl From<GltfError> for SceneError {
+	fn from(err: GltfError) -> Self {
+		SceneError::Gltf(err)
+	}
+}
+
+impl From<PlyError> for SceneError {
+	fn from(err: PlyError) -> Self {
+		SceneError::Ply(err)
+	}
+}
+
+impl From<StlError> for SceneError {
+	fn from(err: StlError) -> Self {
+		SceneError::Stl(err)
+	}
+}
+
+impl From<VoxelGridError> for SceneError {
+	fn from(err: VoxelGridError) -> Self {
+		SceneError::Volume(err)
+	}
+}
+
+// Scene file layout. The file is written in TOML:
+//
+//   [camera]
+//   image_width = 400
+//   look_from = [13.0, 2.0, 3.0]
+//
+//   [background]
+//   type = "solid"
+//   color = [0.7, 0.8, 1.0]
+//
+//   [textures.checker]
+//   type = "checker"
+//   scale = 0.5
+//   space = "spatial"				# Or "uv" for squares on the surfaces
+//   even = [0.2, 0.3, 0.1]
+//   odd = "marble"					# Colors or names of other textures
+//
+//   [textures.marble]
+//   type = "image"
+//   path = "textures/marble.png"	# Relative to the scene file
+//   color_space = "srgb"			# Or "linear" for data maps
+//   wrap = "repeat"				# Or "clamp", "mirror"
+//   filter = "trilinear"			# Or "nearest", "bilinear"
+//
+//   [textures.veins]
+//   type = "marble"				# Or "perlin", "turbulence", "wood"
+//   scale = 4.0					# Multiplies the coordinates, marble veins repeat every 2π / scale
+//   seed = 7						# Optional, the same seed gives the same noise
+//   low = [0.2, 0.2, 0.3]			# Optional, black and white by default
+//   high = [0.9, 0.9, 0.9]
+//
+//   [materials.ground]
+//   type = "lambertian"
+//   albedo = "checker"				# Colors or texture names
+//
+//   [materials.brushed]
+//   type = "metal"
+//   albedo = [0.8, 0.8, 0.8]
+//   fuzz = "veins"					# Numbers or texture names, as index_of_refraction
+//
+//   [materials.frosted]
+//   type = "dielectric"
+//   index_of_refraction = 1.5
+//   roughness = 0.3				# Optional, numbers or texture names, 0 is smooth
+//   absorption = [0.0, 0.4, 0.8]	# Optional, per unit of distance traveled inside
+//
+//   [materials.gold]
+//   type = "conductor"
+//   metal = "gold"					# Or "copper", "aluminum", "silver"
+//   roughness = 0.3				# Numbers or texture names, 0 is a mirror
+//
+//   [materials.steel]
+//   type = "conductor"
+//   eta = [2.9, 2.9, 2.6]			# Complex index of refraction, or a color
+//   k = [3.1, 2.9, 2.8]			# as reflectance = [0.6, 0.6, 0.6]
+//   roughness = 0.2
+//
+//   [materials.lacquer]
+//   type = "principled"
+//   base_color = [0.6, 0.1, 0.1]	# Every other parameter is optional, numbers or
+//   roughness = 0.4					# texture names: metallic, roughness, specular,
+//   clearcoat = 1.0					# transmission, ior, clearcoat, clearcoat_roughness
+//   sheen = 0.0						# and sheen
+//
+//   [materials.fog]
+//   type = "medium"					# Objects of this material are filled with it
+//   density = 0.5
+//   albedo = [0.9, 0.9, 0.9]
+//   anisotropy = 0.6				# Optional, from -1 (backward) to 1 (forward), 0 by default
+//
+//   [[objects]]
+//   type = "sphere"
+//   center = [0.0, -1000.0, 0.0]
+//   radius = 1000.0
+//   material = "ground"
+//
+//   [[objects]]
+//   type = "triangle"
+//   vertices = [[0.0, 1.0, 0.0], [1.0, 1.0, 0.0], [0.0, 2.0, 0.0]]
+//   material = "ground"
+//
+//   [[objects]]
+//   type = "quad"
+//   q = [-1.0, 4.0, -1.0]
+//   u = [2.0, 0.0, 0.0]
+//   v = [0.0, 0.0, 2.0]
+//   material = "ground"
+//
+//   [[objects]]
+//   type = "obj"
+//   path = "models/teapot.obj"		# Relative to the scene file
+//   material = "ground"			# Optional, overrides the MTL materials
+//
+//   [[objects]]
+//   type = "gltf"					# Both .gltf and .glb files
+//   path = "models/room.glb"
+//   camera = true					# Optional, view from the first camera of the file
+//
+//   [[objects]]
+//   type = "ply"					# Or "stl"
+//   path = "models/bunny.ply"
+//   material = "ground"			# Optional, light grey diffuse by default
+//
+//   [[objects]]
+//   type = "volume"
+//   density = "volumes/fire.nrrd"	# Voxel grid scaling the density of the material
+//   min = [-1.0, 0.0, -1.0]			# Corners of the box the grid fills
+//   max = [1.0, 2.0, 1.0]
+//   material = "fog"				# Must be a medium
+//   temperature = "volumes/fire_temperature.nrrd"	# Optional, black body emission
+//   temperature_scale = 1500.0		# Optional, Kelvin of a grid value of 1
+//   blackbody_intensity = 1.0		# Optional
+//   emission = "volumes/glow.raw"	# Optional, radiance times emission_color
+//   emission_color = [1.0, 0.5, 0.1]
+//   size = [64, 64, 64]				# Only for raw files, of voxel_type "uint8" by default,
+//   voxel_type = "float32"			# or "int8" to "int32", "uint16", "uint32", "float64"
+//
+// Any object takes an optional transform, scaling first, then rotating around X,
+// Y and Z in degrees, then translating. Objects loading the same model file with
+// the same material share its meshes:
+//
+//   transform = { scale = 2.0, rotate = [0.0, 45.0, 0.0], translate = [1.0, 0.0, 0.0] }
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SceneDescription {
+	#[serde(default)]
+	camera: CameraSettings,
+	#[serde(default)]
+	background: Background,
+	#[serde(default)]
+	textures: HashMap<String, Spanned<TextureDescription>>,
+	#[serde(default)]
+	materials: HashMap<String, Spanned<MaterialDescription>>,
+	#[serde(default)]
+	objects: Vec<Spanned<ObjectDescription>>,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+enum MaterialDescription {
+	Lambertian { albedo: ColorDescription },
+	Metal { albedo: ColorDescription, #[serde(default)] fuzz: ScalarDescription },
+	Conductor {
+		metal: Option<ConductorPreset>,
+		eta: Option<Color>,
+		k: Option<Color>,
+		reflectance: Option<ColorDescription>,
+		#[serde(default)] roughness: ScalarDescription
+	},
+	Dielectric {
+		index_of_refraction: ScalarDescription,
+		#[serde(default)] roughness: ScalarDescription,
+		absorption: Option<Color>
+	},
+	DiffuseLight { emit: ColorDescription },
+	Principled {
+		base_color: ColorDescription,
+		metallic: Option<ScalarDescription>,
+		roughness: Option<ScalarDescription>,
+		specular: Option<ScalarDescription>,
+		transmission: Option<ScalarDescription>,
+		ior: Option<ScalarDescription>,
+		clearcoat: Option<ScalarDescription>,
+		clearcoat_roughness: Option<ScalarDescription>,
+		sheen: Option<ScalarDescription>
+	},
+	Medium { density: f64, albedo: ColorDescription, #[serde(default)] anisotropy: f64 },
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase", deny_unknown_fields)]
+enum TextureDescription {
+	Solid { color: Color },
+	Checker { scale: f64, #[serde(default)] space: CheckerSpaceDescription, even: ColorDescription, odd: ColorDescription },
+	Image {
+		path: PathBuf,
+		#[serde(default)] color_space: ColorSpace,
+		#[serde(default)] wrap: WrapMode,
+		#[serde(default)] filter: FilterMode
+	},
+	Perlin(NoiseDescription),
+	Turbulence(NoiseDescription),
+	Marble(NoiseDescription),
+	Wood(NoiseDescription),
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct NoiseDescription {
+	scale: f64,
+	#[serde(default)]
+	seed: u64,
+	octaves: Option<u32>,
+	low: Option<Color>,
+	high: Option<Color>,
+}
+
+impl NoiseDescription {
+	fn build(&self, noise: fn(u64, f64) -> NoiseTexture) -> NoiseTexture {
+		let mut texture: NoiseTexture = noise(self.seed, self.scale)
+			.with_colors(self.low.unwrap_or(BLACK), self.high.unwrap_or(WHITE));
+		if let Some(octaves) = self.octaves {
+			texture = texture.with_octaves(octaves);
+		}
+		texture
+	}
+}
+
+#[derive(Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum CheckerSpaceDescription {
+	Uv,
+	#[default]
+	Spatial,
+}
+
+// Either a constant color or the name of a texture
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ColorDescription {
+	Color(Color),
+	Texture(String),
+}
+
+// Either a constant number or the name of a texture, read from the mean of its channels
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ScalarDescription {
+	Value(f64),
+	Texture(String),
+}
+
+impl Default for ScalarDescription {
+	fn default() -> ScalarDescription {
+		ScalarDescription::Value(0.0)
+	}
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase", deny_unknown_fields)]
+enum ObjectDescription {
+	Sphere { center: Point3, radius: f64, material: String, transform: Option<TransformDescription> },
+	Quad { q: Point3, u: Vec3, v: Vec3, material: String, transform: Option<TransformDescription> },
+	Triangle { vertices: [Point3; 3], material: String, transform: Option<TransformDescription> },
+	Obj { path: PathBuf, material: Option<String>, transform: Option<TransformDescription> },
+	Gltf { path: PathBuf, material: Option<String>, #[serde(default)] camera: bool, transform: Option<TransformDescription> },
+	Ply { path: PathBuf, material: Option<String>, transform: Option<TransformDescription> },
+	Stl { path: PathBuf, material: Option<String>, transform: Option<TransformDescription> },
+	Volume {
+		density: PathBuf,
+		min: Point3,
+		max: Point3,
+		material: String,
+		size: Option<[usize; 3]>,
+		#[serde(default)] voxel_type: VoxelType,
+		emission: Option<PathBuf>,
+		emission_color: Option<Color>,
+		temperature: Option<PathBuf>,
+		temperature_scale: Option<f64>,
+		blackbody_intensity: Option<f64>,
+		transform: Option<TransformDescription>
+	},
+}
+
+impl ObjectDescription {
+	fn material(&self) -> Option<&str> {
+		match self {
+			ObjectDescription::Sphere { material, .. } => Some(material),
+			ObjectDescription::Quad { material, .. } => Some(material),
+			ObjectDescription::Triangle { material, .. } => Some(material),
+			ObjectDescription::Obj { material, .. } => material.as_deref(),
+			ObjectDescription::Gltf { material, .. } => material.as_deref(),
+			ObjectDescription::Ply { material, .. } => material.as_deref(),
+			ObjectDescription::Stl { material, .. } => material.as_deref(),
+			ObjectDescription::Volume { material, .. } => Some(material)
+		}
+	}
+
+	fn transform(&self) -> Option<&TransformDescription> {
+		match self {
+			ObjectDescription::Sphere { transform, .. } => transform.as_ref(),
+			ObjectDescription::Quad { transform, .. } => transform.as_ref(),
+			ObjectDescription::Triangle { transform, .. } => transform.as_ref(),
+			ObjectDescription::Obj { transform, .. } => transform.as_ref(),
+			ObjectDescription::Gltf { transform, .. } => transform.as_ref(),
+			ObjectDescription::Ply { transform, .. } => transform.as_ref(),
+			ObjectDescription::Stl { transform, .. } => transform.as_ref(),
+			ObjectDescription::Volume { transform, .. } => transform.as_ref()
+		}
+	}
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TransformDescription {
+	scale: Option<ScaleDescription>,
+	rotate: Option<Vec3>,
+	translate: Option<Vec3>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ScaleDescription {
+	Uniform(f64),
+	Axes(Vec3),
+}
+
+impl TransformDescription {
+	fn matrix(&self) -> Mat4 {
+		let scale: Vec3 = match self.scale {
+			Some(ScaleDescription::Uniform(factor)) => factor * Vec3::ones(),
+			Some(ScaleDescription::Axes(factors)) => factors,
+			None => Vec3::ones()
+		};
+		let rotate: Vec3 = self.rotate.unwrap_or(Vec3::zeros());
+
+		Mat4::translation(self.translate.unwrap_or(Vec3::zeros()))
+			* Mat4::rotation(Vec3::new(0.0, 0.0, 1.0), rotate.z)
+			* Mat4::rotation(Vec3::new(0.0, 1.0, 0.0), rotate.y)
+			* Mat4::rotation(Vec3::new(1.0, 0.0, 0.0), rotate.x)
+			* Mat4::scale(scale)
+	}
+}
+
+#[derive(Clone, Copy)]
+enum ModelFormat {
+	Obj,
+	Gltf,
+	Ply,
+	Stl,
+}
+
+impl ModelFormat {
+	fn from_path(path: &Path) -> Option<ModelFormat> {
+		let extension: String = path.extension()?.to_string_lossy().to_lowercase();
+		match extension.as_str() {
+			"obj" => Some(ModelFormat::Obj),
+			"gltf" | "glb" => Some(ModelFormat::Gltf),
+			"ply" => Some(ModelFormat::Ply),
+			"stl" => Some(ModelFormat::Stl),
+			_ => None
+		}
+	}
+}
+
+// Meshes of a model file, with the camera of glTF files
+struct Model {
+	meshes: Vec<Arc<dyn Hitable>>,
+	camera: Option<GltfCamera>,
+	warnings: Vec<String>,
+}
+
+/// Load a scene file, or a glTF file rendered from its own camera
+pub fn load_scene(path: &Path) -> Result<Scene, SceneError> {
+	if path.extension().is_some_and(|extension| extension.eq_ignore_ascii_case("gltf") || extension.eq_ignore_ascii_case("glb")) {
+		return load_gltf_scene(path);
+	}
+
+	let source: String = fs::read_to_string(path)?;
+	parse_scene(&source, path.parent().unwrap_or(Path::new("")))
+}
+
+/// Scene described by the source of a scene file. Model paths are relative to `base_dir`
+pub fn parse_scene(source: &str, base_dir: &Path) -> Result<Scene, SceneError> {
+	let mut description: SceneDescription = toml::from_str(source)
+		.map_err(|err| parse_error(source, err.span().map_or(0, |span| span.start), err.message()))?;
+
+	let mut textures: Textures = Textures {
+		source,
+		base_dir,
+		descriptions: &description.textures,
+		built: HashMap::new(),
+		pending: Vec::new()
+	};
+	let mut materials: HashMap<String, Arc<dyn Material>> = HashMap::new();
+	let mut emissive_materials: HashSet<&str> = HashSet::new();
+	let mut media: HashMap<&str, f64> = HashMap::new();
+	for (name, material) in description.materials.iter() {
+		materials.insert(name.clone(), build_material(source, material, &mut textures)?);
+		match material.get_ref() {
+			MaterialDescription::DiffuseLight { .. } => { emissive_materials.insert(name); },
+			MaterialDescription::Medium { density, .. } => { media.insert(name, *density); },
+			_ => ()
+		}
+	}
+
+	let mut world: HitableList = HitableList::new(Vec::new());
+	let mut lights: LightList = LightList::default();
+	let mut warnings: Vec<String> = Vec::new();
+	let mut models: HashMap<(PathBuf, Option<&str>), Model> = HashMap::new();
+	for object in description.objects.iter() {
+		let material_name: Option<&str> = object.get_ref().material();
+		let material: Option<Arc<dyn Material>> = match material_name {
+			Some(material_name) => Some(materials.get(material_name)
+				.ok_or_else(|| parse_error(source, object.span().start, &format!("unknown material '{}'", material_name)))?
+				.clone()),
+			None => None
+		};
+		let is_light: bool = material_name.is_some_and(|name| emissive_materials.contains(name));
+
+		// Objects of a medium material are only its boundary, filled with the medium.
+		// Volumes bring their own density
+		let is_volume: bool = matches!(object.get_ref(), ObjectDescription::Volume { .. });
+		let medium: Option<(f64, Arc<dyn Material>)> = material_name
+			.filter(|_| !is_volume)
+			.and_then(|name| media.get(name))
+			.zip(material.clone())
+			.map(|(density, phase_function)| (*density, phase_function));
+
+		let transform: Option<Mat4> = object.get_ref().transform().map(TransformDescription::matrix);
+		if transform.is_some_and(|transform| transform.inverse().is_none()) {
+			return Err(parse_error(source, object.span().start, "transform must not scale by zero"));
+		}
+		let placed = |object: Arc<dyn Hitable>| -> Arc<dyn Hitable> {
+			let object: Arc<dyn Hitable> = match transform {
+				Some(transform) => Arc::new(Instance::new(object, transform)),
+				None => object
+			};
+			match &medium {
+				Some((density, phase_function)) => Arc::new(ConstantMedium::from_phase_function(object, *density, phase_function.clone())),
+				None => object
+			}
+		};
+
+		let (path, format) = match object.get_ref() {
+			ObjectDescription::Obj { path, .. } => (path, ModelFormat::Obj),
+			ObjectDescription::Gltf { path, .. } => (path, ModelFormat::Gltf),
+			ObjectDescription::Ply { path, .. } => (path, ModelFormat::Ply),
+			ObjectDescription::Stl { path, .. } => (path, ModelFormat::Stl),
+			ObjectDescription::Volume { .. } => {
+				let density_scale: f64 = *material_name.and_then(|name| media.get(name))
+					.ok_or_else(|| parse_error(source, object.span().start, "volume material must be a medium"))?;
+				let volume: GridMedium = build_volume(base_dir, object.get_ref(), density_scale, material.unwrap())
+					.map_err(|err| parse_error(source, object.span().start, &err.to_string()))?;
+				world.add(placed(Arc::new(volume)));
+				continue;
+			},
+			primitive => {
+				let primitive: Arc<dyn Hitable> = build_primitive(source, object.span().start, primitive, material.unwrap(), &mut lights, is_light && transform.is_none())?;
+				if is_light && transform.is_some() {
+					warnings.push(format!("line {}: transformed lights are not sampled directly", location(source, object.span().start).0));
+				}
+				world.add(placed(primitive));
+				continue;
+			}
+		};
+
+		let model: &Model = match models.entry((path.clone(), material_name)) {
+			Entry::Occupied(entry) => entry.into_mut(),
+			Entry::Vacant(entry) => {
+				let mut model: Model = load_model(&base_dir.join(path), format, material)
+					.map_err(|err| parse_error(source, object.span().start, &err.to_string()))?;
+				if is_light {
+					warnings.push(format!("{}: emissive meshes are not sampled as lights", path.display()));
+				}
+				warnings.append(&mut model.warnings);
+				entry.insert(model)
+			}
+		};
+
+		for mesh in model.meshes.iter() {
+			world.add(placed(mesh.clone()));
+		}
+		if let ObjectDescription::Gltf { camera: true, .. } = object.get_ref() {
+			match &model.camera {
+				Some(gltf_camera) => gltf_camera.transformed(&transform.unwrap_or_default()).apply(&mut description.camera),
+				None => warnings.push(format!("{}: no camera to view from", path.display()))
+			}
+		}
+	}
+
+	Ok(Scene {
+		camera: description.camera,
+		world,
+		lights,
+		background: description.background,
+		warnings
+	})
+}
+
+// glTF file viewed from its first camera. Without camera the whole scene is framed
+fn load_gltf_scene(path: &Path) -> Result<Scene, SceneError> {
+	let model: Model = load_model(path, ModelFormat::Gltf, None)?;
+
+	let world: HitableList = HitableList::new(model.meshes);
+
+	let mut camera: CameraSettings = CameraSettings { defocus_angle: 0.0, ..CameraSettings::default() };
+	let mut warnings: Vec<String> = model.warnings;
+	match &model.camera {
+		Some(gltf_camera) => gltf_camera.apply(&mut camera),
+		None => {
+			warnings.push(format!("{}: no camera, framing the whole scene", path.display()));
+			if !world.bounding_box().is_empty() {
+				camera.frame(&world.bounding_box());
+			}
+		}
+	}
+
+	Ok(Scene {
+		camera,
+		world,
+		lights: LightList::default(),
+		background: Background::default(),
+		warnings
+	})
+}
+
+/// Studio to look at a model file: the model rests on a large floor, lit from
+/// above by a key and a fill area light, with the camera framing it
+pub fn studio_scene(path: &Path) -> Result<Scene, SceneError> {
+	let format: ModelFormat = ModelFormat::from_path(path)
+		.ok_or_else(|| SceneError::UnsupportedModel(path.to_path_buf()))?;
+	let model: Model = load_model(path, format, None)?;
+
+	let mut world: HitableList = HitableList::new(model.meshes);
+	let bounds: Aabb = world.bounding_box();
+	if bounds.is_empty() {
+		return Err(SceneError::UnsupportedModel(path.to_path_buf()));
+	}
+
+	// Everything is sized after the radius of the model
+	let center: Point3 = bounds.centroid();
+	let radius: f64 = 0.5 * bounds.extent().length();
+	let floor: Arc<dyn Material> = Arc::new(Lambertian::new(Color::new(0.6, 0.6, 0.6)));
+	world.add(Arc::new(Quad::new(
+		Point3::new(center.x - 50.0 * radius, bounds.min.y, center.z - 50.0 * radius),
+		Vec3::new(100.0 * radius, 0.0, 0.0),
+		Vec3::new(0.0, 0.0, 100.0 * radius),
+		floor
+	)));
+
+	let mut lights: LightList = LightList::default();
+	for (offset, size, radiance) in [(Vec3::new(-2.0, 3.0, 2.0), 2.0, 8.0), (Vec3::new(3.0, 2.0, 1.0), 1.5, 3.0)] {
+		let corner: Point3 = center + radius * offset - 0.5 * size * radius * Vec3::new(1.0, 0.0, 1.0);
+		let light: Arc<Quad> = Arc::new(Quad::new(
+			corner,
+			Vec3::new(size * radius, 0.0, 0.0),
+			Vec3::new(0.0, 0.0, size * radius),
+			Arc::new(DiffuseLight::new(radiance * WHITE))
+		));
+		world.add(add_light(&mut lights, light, true));
+	}
+
+	let mut camera: CameraSettings = CameraSettings {
+		look_from: center + Vec3::new(1.0, 0.6, 1.6),
+		look_at: center,
+		defocus_angle: 0.0,
+		..CameraSettings::default()
+	};
+	camera.frame(&bounds);
+
+	Ok(Scene {
+		camera,
+		world,
+		lights,
+		background: Background::Solid { color: Color::new(0.05, 0.05, 0.05) },
+		warnings: model.warnings
+	})
+}
+
+/// Final scene of "Ray Tracing in One Weekend": a field of small random spheres
+/// around a glass, a diffuse and a metal large one, drawn from the camera seed
+pub fn random_spheres_scene() -> Scene {
+	let camera: CameraSettings = CameraSettings::default();
+	let mut rng: Rng = Rng::new(camera.seed);
+
+	let mut world: HitableList = HitableList::new(vec![
+		Arc::new(Sphere::new(Point3::new(0.0, -1000.0, 0.0), 1000.0, Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5)))))
+	]);
+	for a in -11..11 {
+		for b in -11..11 {
+			let choose_material: f64 = rng.f64();
+			let center: Point3 = Point3::new(a as f64 + 0.9 * rng.f64(), 0.2, b as f64 + 0.9 * rng.f64());
+			if (center - Point3::new(4.0, 0.2, 0.0)).length() <= 0.9 {
+				continue;
+			}
+
+			let material: Arc<dyn Material> = if choose_material < 0.8 {
+				Arc::new(Lambertian::new(Color::random(&mut rng) * Color::random(&mut rng)))
+			} else if choose_material < 0.95 {
+				Arc::new(Metal::new(Color::random_range(&mut rng, 0.5, 1.0), rng.f64_range(0.0, 0.5)))
+			} else {
+				Arc::new(Dielectric::new(1.5))
+			};
+			world.add(Arc::new(Sphere::new(center, 0.2, material)));
+		}
+	}
+
+	world.add(Arc::new(Sphere::new(Point3::new(0.0, 1.0, 0.0), 1.0, Arc::new(Dielectric::new(1.5)))));
+	world.add(Arc::new(Sphere::new(Point3::new(-4.0, 1.0, 0.0), 1.0, Arc::new(Lambertian::new(Color::new(0.4, 0.2, 0.1))))));
+	world.add(Arc::new(Sphere::new(Point3::new(4.0, 1.0, 0.0), 1.0, Arc::new(Metal::new(Color::new(0.7, 0.6, 0.5), 0.0)))));
+
+	Scene {
+		camera,
+		world,
+		lights: LightList::default(),
+		background: Background::default(),
+		warnings: Vec::new()
+	}
+}
+
+// Meshes of a model file. OBJ and glTF files come with their own materials, which
+// the given material overrides. The others take it, or a light grey diffuse one
+fn load_model(path: &Path, format: ModelFormat, material: Option<Arc<dyn Material>>) -> Result<Model, SceneError> {
+	let plain_material: Arc<dyn Material> = material.clone()
+		.unwrap_or_else(|| Arc::new(Lambertian::new(Color::new(0.8, 0.8, 0.8))));
+
+	let (meshes, camera, warnings) = match format {
+		ModelFormat::Obj => {
+			let model: ObjModel = load_obj(path)?;
+			(model.groups.into_iter().map(|group| group.mesh).collect(), None, model.warnings)
+		},
+		ModelFormat::Gltf => {
+			let model: GltfModel = load_gltf(path)?;
+			(model.meshes, model.camera, model.warnings)
+		},
+		ModelFormat::Ply => (vec![load_ply(path, plain_material)?], None, Vec::new()),
+		ModelFormat::Stl => (vec![load_stl(path, plain_material)?], None, Vec::new())
+	};
+
+	let meshes: Vec<Arc<dyn Hitable>> = meshes.into_iter()
+		.map(|mesh: TriangleMesh| -> Arc<dyn Hitable> {
+			match &material {
+				Some(material) => Arc::new(mesh.with_material(material.clone())),
+				None => Arc::new(mesh)
+			}
+		})
+		.collect();
+
+	Ok(Model { meshes, camera, warnings })
+}
+
+// Sphere, quad or triangle, added to the lights when emissive
+fn build_primitive(source: &str, offset: usize, object: &ObjectDescription, material: Arc<dyn Material>, lights: &mut LightList, is_light: bool) -> Result<Arc<dyn Hitable>, SceneError> {
+	match object {
+		ObjectDescription::Sphere { center, radius, .. } => {
+			let sphere: Arc<Sphere> = Arc::new(Sphere::new(*center, *radius, material));
+			Ok(add_light(lights, sphere, is_light))
+		},
+		ObjectDescription::Quad { q, u, v, .. } => {
+			if cross(*u, *v).length_squared() == 0.0 {
+				return Err(parse_error(source, offset, "quad sides u and v must not be parallel"));
+			}
+			let quad: Arc<Quad> = Arc::new(Quad::new(*q, *u, *v, material));
+			Ok(add_light(lights, quad, is_light))
+		},
+		ObjectDescription::Triangle { vertices, .. } => {
+			let [p0, p1, p2] = *vertices;
+			if cross(p1 - p0, p2 - p0).length_squared() == 0.0 {
+				return Err(parse_error(source, offset, "triangle vertices must not be aligned"));
+			}
+			let triangle: Arc<Triangle> = Arc::new(Triangle::new(p0, p1, p2, material));
+			Ok(add_light(lights, triangle, is_light))
+		},
+		_ => unreachable!("model files are not primitives")
+	}
+}
+
+// Medium of the voxel grids of a volume object, whose density scales the grid values
+fn build_volume(base_dir: &Path, object: &ObjectDescription, density_scale: f64, phase_function: Arc<dyn Material>) -> Result<GridMedium, SceneError> {
+	let ObjectDescription::Volume {
+		density, min, max, size, voxel_type, emission, emission_color, temperature, temperature_scale, blackbody_intensity, ..
+	} = object else {
+		unreachable!("only volumes have voxel grids")
+	};
+	if min.x >= max.x || min.y >= max.y || min.z >= max.z {
+		return Err(SceneError::Volume(VoxelGridError::Parse {
+			path: density.clone(),
+			message: "min must be below max along every axis".to_string()
+		}));
+	}
+
+	// NRRD headers describe the voxels, raw files take the size and type of the object
+	let load = |path: &PathBuf| -> Result<Arc<VoxelGrid>, SceneError> {
+		let full_path: PathBuf = base_dir.join(path);
+		let is_raw: bool = full_path.extension().is_some_and(|extension| extension.eq_ignore_ascii_case("raw"));
+		let grid: VoxelGrid = match (is_raw, size) {
+			(true, Some(size)) => load_raw(&full_path, *size, *voxel_type)?,
+			(true, None) => return Err(SceneError::Volume(VoxelGridError::Parse {
+				path: path.clone(),
+				message: "raw voxel files need a size".to_string()
+			})),
+			(false, _) => load_nrrd(&full_path)?
+		};
+		Ok(Arc::new(grid))
+	};
+
+	let mut volume: GridMedium = GridMedium::new(Aabb::new(*min, *max), load(density)?, density_scale, phase_function);
+	if let Some(emission) = emission {
+		volume = volume.with_emission(load(emission)?, emission_color.unwrap_or(WHITE));
+	}
+	if let Some(temperature) = temperature {
+		volume = volume.with_temperature(load(temperature)?, temperature_scale.unwrap_or(1.0), blackbody_intensity.unwrap_or(1.0));
+	}
+	Ok(volume)
+}
+
+fn add_light<T>(lights: &mut LightList, object: Arc<T>, is_light: bool) -> Arc<dyn Hitable>
+where T: Hitable + Light + 'static {
+	if is_light {
+		lights.add(object.clone());
+	}
+	object
+}
+
+fn build_material<'a>(source: &str, material: &'a Spanned<MaterialDescription>, textures: &mut Textures<'a>) -> Result<Arc<dyn Material>, SceneError> {
+	let offset: usize = material.span().start;
+	match material.get_ref() {
+		MaterialDescription::Lambertian { albedo } => Ok(Arc::new(Lambertian::from_texture(textures.color(albedo, offset)?))),
+		MaterialDescription::Metal { albedo, fuzz } =>
+			Ok(Arc::new(Metal::from_textures(textures.color(albedo, offset)?, textures.scalar(fuzz, offset)?))),
+		MaterialDescription::Conductor { metal, eta, k, reflectance, roughness } => {
+			let conductor: Conductor = match (metal, eta, k, reflectance) {
+				(Some(metal), None, None, None) => Conductor::from_preset(*metal, 0.0),
+				(None, Some(eta), Some(k), None) => Conductor::new(*eta, *k, 0.0),
+				(None, None, None, Some(reflectance)) => Conductor::from_reflectance(textures.color(reflectance, offset)?, 0.0),
+				_ => return Err(parse_error(source, offset, "conductor needs either a metal, both eta and k, or a reflectance"))
+			};
+			Ok(Arc::new(conductor.with_roughness(textures.scalar(roughness, offset)?)))
+		},
+		MaterialDescription::Dielectric { index_of_refraction, roughness, absorption } => {
+			if matches!(index_of_refraction, ScalarDescription::Value(index) if *index <= 0.0) {
+				return Err(parse_error(source, material.span().start, "index_of_refraction must be positive"));
+			}
+			if absorption.is_some_and(|absorption| absorption.x.min(absorption.y).min(absorption.z) < 0.0) {
+				return Err(parse_error(source, offset, "absorption must not be negative"));
+			}
+			let dielectric: Dielectric = Dielectric::from_texture(textures.scalar(index_of_refraction, offset)?)
+				.with_roughness(textures.scalar(roughness, offset)?)
+				.with_absorption(absorption.unwrap_or(BLACK));
+			Ok(Arc::new(dielectric))
+		},
+		MaterialDescription::DiffuseLight { emit } => Ok(Arc::new(DiffuseLight::from_texture(textures.color(emit, offset)?))),
+		MaterialDescription::Principled {
+			base_color, metallic, roughness, specular, transmission, ior, clearcoat, clearcoat_roughness, sheen
+		} => {
+			if matches!(ior, Some(ScalarDescription::Value(index)) if *index <= 0.0) {
+				return Err(parse_error(source, offset, "ior must be positive"));
+			}
+			// Parameters left out keep the defaults of the material
+			let mut principled: Principled = Principled::new(textures.color(base_color, offset)?);
+			if let Some(metallic) = metallic {
+				principled = principled.with_metallic(textures.scalar(metallic, offset)?);
+			}
+			if let Some(roughness) = roughness {
+				principled = principled.with_roughness(textures.scalar(roughness, offset)?);
+			}
+			if let Some(specular) = specular {
+				principled = principled.with_specular(textures.scalar(specular, offset)?);
+			}
+			if let Some(transmission) = transmission {
+				principled = principled.with_transmission(textures.scalar(transmission, offset)?);
+			}
+			if let Some(ior) = ior {
+				principled = principled.with_ior(textures.scalar(ior, offset)?);
+			}
+			if let Some(clearcoat) = clearcoat {
+				principled = principled.with_clearcoat(textures.scalar(clearcoat, offset)?);
+			}
+			if let Some(clearcoat_roughness) = clearcoat_roughness {
+				principled = principled.with_clearcoat_roughness(textures.scalar(clearcoat_roughness, offset)?);
+			}
+			if let Some(sheen) = sheen {
+				principled = principled.with_sheen(textures.scalar(sheen, offset)?);
+			}
+			Ok(Arc::new(principled))
+		},
+		MaterialDescription::Medium { density, albedo, anisotropy } => {
+			if *density <= 0.0 {
+				return Err(parse_error(source, offset, "density must be positive"));
+			}
+			if anisotropy.abs() >= 1.0 {
+				return Err(parse_error(source, offset, "anisotropy must be between -1 and 1"));
+			}
+			let albedo: Arc<dyn Texture> = textures.color(albedo, offset)?;
+			if *anisotropy == 0.0 {
+				Ok(Arc::new(Isotropic::from_texture(albedo)))
+			} else {
+				Ok(Arc::new(HenyeyGreenstein::from_texture(albedo, *anisotropy)))
+			}
+		}
+	}
+}
+
+// Textures of the scene file, built the first time they are referenced so each
+// image is loaded once. Names being resolved are pending, to catch cycles
+struct Textures<'a> {
+	source: &'a str,
+	base_dir: &'a Path,
+	descriptions: &'a HashMap<String, Spanned<TextureDescription>>,
+	built: HashMap<&'a str, Arc<dyn Texture>>,
+	pending: Vec<&'a str>,
+}
+
+impl<'a> Textures<'a> {
+	// Texture of a color or texture name, errors located at `offset`
+	fn color(&mut self, color: &'a ColorDescription, offset: usize) -> Result<Arc<dyn Texture>, SceneError> {
+		match color {
+			ColorDescription::Color(color) => Ok(Arc::new(SolidColor::new(*color))),
+			ColorDescription::Texture(name) => self.texture(name, offset)
+		}
+	}
+
+	// Texture of a number or texture name, a grey of the number for constants
+	fn scalar(&mut self, scalar: &'a ScalarDescription, offset: usize) -> Result<Arc<dyn Texture>, SceneError> {
+		match scalar {
+			ScalarDescription::Value(value) => Ok(Arc::new(SolidColor::new(Color::new(*value, *value, *value)))),
+			ScalarDescription::Texture(name) => self.texture(name, offset)
+		}
+	}
+
+	fn texture(&mut self, name: &'a str, offset: usize) -> Result<Arc<dyn Texture>, SceneError> {
+		if let Some(texture) = self.built.get(name) {
+			return Ok(texture.clone());
+		}
+		let (key, description) = self.descriptions.get_key_value(name)
+			.ok_or_else(|| parse_error(self.source, offset, &format!("unknown texture '{}'", name)))?;
+		if self.pending.contains(&name) {
+			return Err(parse_error(self.source, offset, &format!("texture '{}' refers to itself", name)));
+		}
+
+		self.pending.push(key);
+		let offset: usize = description.span().start;
+		let texture: Arc<dyn Texture> = match description.get_ref() {
+			TextureDescription::Solid { color } => Arc::new(SolidColor::new(*color)),
+			TextureDescription::Checker { scale, space, even, odd } => {
+				if *scale <= 0.0 {
+					return Err(parse_error(self.source, offset, "scale must be positive"));
+				}
+				let even: Arc<dyn Texture> = self.color(even, offset)?;
+				let odd: Arc<dyn Texture> = self.color(odd, offset)?;
+				match space {
+					CheckerSpaceDescription::Uv => Arc::new(CheckerTexture::uv(*scale, even, odd)),
+					CheckerSpaceDescription::Spatial => Arc::new(CheckerTexture::spatial(*scale, even, odd))
+				}
+			},
+			TextureDescription::Image { path, color_space, wrap, filter } => {
+				let path: PathBuf = self.base_dir.join(path);
+				let texture: ImageTexture = ImageTexture::load(&path, *color_space).map_err(|err| SceneError::Image(path, err))?;
+				Arc::new(texture.with_wrap(*wrap, *wrap).with_filter(*filter))
+			},
+			TextureDescription::Perlin(noise) => Arc::new(noise.build(NoiseTexture::perlin)),
+			TextureDescription::Turbulence(noise) => Arc::new(noise.build(NoiseTexture::turbulence)),
+			TextureDescription::Marble(noise) => Arc::new(noise.build(NoiseTexture::marble)),
+			TextureDescription::Wood(noise) => Arc::new(noise.build(NoiseTexture::wood))
+		};
+		self.pending.pop();
+
+		self.built.insert(key, texture.clone());
+		Ok(texture)
+	}
+}
+
+// Error located at the byte offset of the source file
+fn parse_error(source: &str, offset: usize, message: &str) -> SceneError {
+	let (line, column) = location(source, offset);
+	SceneError::Parse {
+		line,
+		column,
+		message: message.trim_end().to_string()
+	}
+}
+
+// Line and column of the byte offset of the source file
+fn location(source: &str, offset: usize) -> (usize, usize) {
+	let before: &str = &source[..offset.min(source.len())];
+	let line_start: usize = before.rfind('\n').map_or(0, |i| i + 1);
+	(before.matches('\n').count() + 1, before[line_start..].chars().count() + 1)
+}
@@ -1,29 +1,57 @@
-use std::rc::Rc;
+use std::{f64::consts::PI, sync::Arc};
 
+use super::aabb::Aabb;
 use super::hit::{HitRecord, Hitable};
 use super::ray::Ray;
 use super::vec::{Point3, Vec3, dot};
-use crate::material::Material;
+use crate::{
+	color::WHITE,
+	light::{Light, LightSample, same_distance},
+	material::Material,
+	onb::Onb,
+	sampler::Sampler
+};
 
 pub struct Sphere {
     center: Point3,
     radius: f64,
-	material: Rc<dyn Material>,
+	material: Arc<dyn Material>,
 }
 
 impl Sphere {
-	pub fn new(center: Point3, radius: f64, material: Rc<dyn Material>) -> Sphere {
+	pub fn new(center: Point3, radius: f64, material: Arc<dyn Material>) -> Sphere {
 		Sphere { 
-			center: center, 
-			radius: radius,
-			material: material
+			center, 
+			radius,
+			material
 		}
 	}
 }
 
-impl Hitable for Sphere {
+/// Surface coordinates of a point of the unit sphere centered at the origin. u is the
+/// angle around the Y axis from X = -1, v the angle from Y = -1 to Y = +1, both in [0, 1]
+pub fn sphere_uv(p: Point3) -> (f64, f64) {
+	let theta: f64 = (-p.y).clamp(-1.0, 1.0).acos();
+	let phi: f64 = (-p.z).atan2(p.x) + PI;
+	(phi / (2.0 * PI), theta / PI)
+}
+
+// Tangents along u and v at the point of outward normal `n` of a sphere of `radius`,
+// derivatives of the mapping of `sphere_uv`. Both poles get a null tangent along u
+fn sphere_tangents(n: Vec3, radius: f64) -> (Vec3, Vec3) {
+	let sin_theta: f64 = (n.x * n.x + n.z * n.z).sqrt();
+	let dpdu: Vec3 = (2.0 * PI * radius) * Vec3::new(n.z, 0.0, -n.x);
+	let dpdv: Vec3 = if sin_theta > 0.0 {
+		(PI * radius) * Vec3::new(-n.x * n.y / sin_theta, sin_theta, -n.y * n.z / sin_theta)
+	} else {
+		Vec3::new(0.0, PI * radius, 0.0)
+	};
+	(dpdu, dpdv)
+}
 
-    fn hit(&self, ray: &Ray, ray_tmin: f64, ray_tmax: f64) -> Option<HitRecord> {
+impl Sphere {
+	// Closest hit of the surface, which the light sampling also needs without a sampler
+    fn intersect(&self, ray: &Ray, ray_tmin: f64, ray_tmax: f64) -> Option<HitRecord> {
         let oc: Vec3 = ray.origin() - self.center;
         let a: f64 = ray.dir().length_squared();
         let half_b: f64 = dot(oc, ray.dir());
@@ -54,14 +82,75 @@ impl Hitable for Sphere {
 		let outward_normal: Vec3 = (hit_point - self.center) / self.radius;
 		let hit_front_face: bool = dot(ray.dir(), outward_normal) < 0.0;
 		let hit_normal: Vec3 = if hit_front_face { outward_normal } else { -outward_normal };
+		let (u, v) = sphere_uv(outward_normal);
+		let (dpdu, dpdv) = sphere_tangents(outward_normal, self.radius);
 
         Some(HitRecord {
 			p: hit_point, 
 			normal: hit_normal,
 			t: hit_t,
+			u,
+			v,
+			dpdu,
+			dpdv,
+			uv_footprint: 0.0,
 			front_face: hit_front_face,
+			color: WHITE,
 			material: self.material.clone()
 		})
-
     }
-}
\ No newline at end of file
+}
+
+impl Hitable for Sphere {
+	fn hit(&self, ray: &Ray, ray_tmin: f64, ray_tmax: f64, _sampler: &mut dyn Sampler) -> Option<HitRecord> {
+		self.intersect(ray, ray_tmin, ray_tmax)
+	}
+
+	fn bounding_box(&self) -> Aabb {
+		let radius: Vec3 = Vec3::ones() * self.radius.abs();
+		Aabb::new(self.center - radius, self.center + radius)
+	}
+}
+
+impl Sphere {
+	// 1 - cos of the half angle of the cone subtended by the sphere, computed without 
+	// cancellation for far away spheres. None when the point is inside the sphere
+	fn one_minus_cos_theta_max(&self, origin: Point3) -> Option<f64> {
+		let sin2_theta_max: f64 = self.radius * self.radius / (self.center - origin).length_squared();
+		if sin2_theta_max >= 1.0 {
+			return None;
+		}
+		Some(sin2_theta_max / (1.0 + (1.0 - sin2_theta_max).sqrt()))
+	}
+}
+
+impl Light for Sphere {
+	fn sample(&self, origin: Point3, sampler: &mut dyn Sampler) -> Option<LightSample> {
+		// Uniform sampling of the cone of directions subtended by the sphere
+		let one_minus_cos_theta_max: f64 = self.one_minus_cos_theta_max(origin)?;
+
+		let (r1, r2) = sampler.get_2d();
+		let phi: f64 = 2.0 * PI * r1;
+		let cos_theta: f64 = 1.0 - r2 * one_minus_cos_theta_max;
+		let sin_theta: f64 = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+
+		let direction: Vec3 = Onb::new(self.center - origin)
+			.local(Vec3::new(phi.cos() * sin_theta, phi.sin() * sin_theta, cos_theta));
+		let hit: HitRecord = self.intersect(&Ray::new(origin, direction), 0.001, f64::INFINITY)?;
+
+		Some(LightSample {
+			direction,
+			distance: hit.t,
+			pdf: 1.0 / (2.0 * PI * one_minus_cos_theta_max)
+		})
+	}
+
+	fn pdf(&self, origin: Point3, direction: Vec3, distance: f64) -> f64 {
+		let on_light: bool = self.intersect(&Ray::new(origin, direction), 0.001, f64::INFINITY)
+			.is_some_and(|hit| same_distance(hit.t, distance));
+		match self.one_minus_cos_theta_max(origin) {
+			Some(one_minus_cos_theta_max) if on_light => 1.0 / (2.0 * PI * one_minus_cos_theta_max),
+			_ => 0.0
+		}
+	}
+}
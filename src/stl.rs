@@ -0,0 +1,108 @@
+use std::{
+	collections::HashMap,
+	fmt,
+	fs,
+	path::{Path, PathBuf},
+	sync::Arc
+};
+
+use crate::{
+	material::Material,
+	mesh::TriangleMesh,
+	vec::Point3
+};
+
+#[derive(Debug)]
+pub enum StlError {
+	Io(PathBuf, std::io::Error),
+	Parse { path: PathBuf, message: String },
+}
+
+impl fmt::Display for StlError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			StlError::Io(path, err) => write!(f, "{}: {}", path.display(), err),
+			StlError::Parse { path, message } => write!(f, "{}: {}", path.display(), message)
+		}
+	}
+}
+
+impl std::error::Error for StlError {}
+
+/// Triangle mesh of an ASCII or binary STL file. Facets repeat their vertices, so
+/// vertices at the same position are merged. Facet normals are dropped, they are
+/// the geometric normals anyway
+pub fn load_stl(path: &Path, material: Arc<dyn Material>) -> Result<TriangleMesh, StlError> {
+	let bytes: Vec<u8> = fs::read(path).map_err(|err| StlError::Io(path.to_path_buf(), err))?;
+	let parse_error = |message: String| StlError::Parse { path: path.to_path_buf(), message };
+
+	// Binary files may also start with "solid", their size tells them apart
+	let binary_triangle_count: Option<usize> = bytes.get(80..84)
+		.map(|count| u32::from_le_bytes([count[0], count[1], count[2], count[3]]) as usize)
+		.filter(|count| bytes.len() == 84 + 50 * count);
+
+	let triangles: Vec<[Point3; 3]> = match binary_triangle_count {
+		Some(count) => parse_binary(&bytes[84..], count),
+		None if bytes.starts_with(b"solid") => {
+			let text: &str = std::str::from_utf8(&bytes).map_err(|_| parse_error("ASCII file is not valid text".to_string()))?;
+			parse_ascii(text).map_err(parse_error)?
+		},
+		None => return Err(parse_error("neither an ASCII nor a binary STL file".to_string()))
+	};
+
+	let mut vertex_indices: HashMap<[u64; 3], u32> = HashMap::new();
+	let mut positions: Vec<Point3> = Vec::new();
+	let indices: Vec<[u32; 3]> = triangles.iter()
+		.map(|triangle| triangle.map(|p| {
+			*vertex_indices.entry([p.x.to_bits(), p.y.to_bits(), p.z.to_bits()]).or_insert_with(|| {
+				positions.push(p);
+				(positions.len() - 1) as u32
+			})
+		}))
+		.collect();
+
+	TriangleMesh::new(positions, indices, material).map_err(parse_error)
+}
+
+// Each triangle is a normal, three vertices and a 16 bit attribute
+fn parse_binary(body: &[u8], count: usize) -> Vec<[Point3; 3]> {
+	let read = |offset: usize| -> f64 {
+		f32::from_le_bytes([body[offset], body[offset + 1], body[offset + 2], body[offset + 3]]) as f64
+	};
+
+	(0..count)
+		.map(|triangle| {
+			let start: usize = triangle * 50 + 12;
+			[0, 1, 2].map(|vertex| {
+				let offset: usize = start + vertex * 12;
+				Point3::new(read(offset), read(offset + 4), read(offset + 8))
+			})
+		})
+		.collect()
+}
+
+fn parse_ascii(text: &str) -> Result<Vec<[Point3; 3]>, String> {
+	let mut triangles: Vec<[Point3; 3]> = Vec::new();
+	let mut facet: Vec<Point3> = Vec::with_capacity(3);
+
+	for (line_index, line) in text.lines().enumerate() {
+		let tokens: Vec<&str> = line.split_whitespace().collect();
+		match tokens[..] {
+			["vertex", x, y, z] => {
+				let coordinate = |value: &str| value.parse::<f64>()
+					.map_err(|_| format!("line {}: '{}' is not a number", line_index + 1, value));
+				facet.push(Point3::new(coordinate(x)?, coordinate(y)?, coordinate(z)?));
+			},
+			["endfacet"] => {
+				match facet[..] {
+					[p0, p1, p2] => triangles.push([p0, p1, p2]),
+					_ => return Err(format!("line {}: facet with {} vertices", line_index + 1, facet.len()))
+				}
+				facet.clear();
+			},
+			_ => {}
+		}
+	}
+
+	Ok(triangles)
+}
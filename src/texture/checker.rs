@@ -0,0 +1,58 @@
+use std::sync::Arc;
+
+use crate::{
+	texture::Texture,
+	color::Color,
+	vec::Point3
+};
+
+#[derive(Clone, Copy, PartialEq)]
+enum CheckerSpace {
+	Uv,			// Squares drawn on the surface coordinates
+	Spatial,	// Cubes filling space, the surfaces cutting through them
+}
+
+/// Alternating squares of two textures
+pub struct CheckerTexture {
+	inv_scale: f64,
+	even: Arc<dyn Texture>,
+	odd: Arc<dyn Texture>,
+	space: CheckerSpace,
+}
+
+impl CheckerTexture {
+	/// Checker of 3D cells with sides of `scale` world units
+	pub fn spatial(scale: f64, even: Arc<dyn Texture>, odd: Arc<dyn Texture>) -> CheckerTexture {
+		CheckerTexture { inv_scale: 1.0 / scale, even, odd, space: CheckerSpace::Spatial }
+	}
+
+	/// Checker of squares with sides of `scale` in surface coordinates
+	pub fn uv(scale: f64, even: Arc<dyn Texture>, odd: Arc<dyn Texture>) -> CheckerTexture {
+		CheckerTexture { inv_scale: 1.0 / scale, even, odd, space: CheckerSpace::Uv }
+	}
+}
+
+impl CheckerTexture {
+	fn is_even(&self, u: f64, v: f64, p: Point3) -> bool {
+		let cell = |x: f64| (self.inv_scale * x).floor() as i64;
+		let sum: i64 = match self.space {
+			CheckerSpace::Uv => cell(u) + cell(v),
+			CheckerSpace::Spatial => cell(p.x) + cell(p.y) + cell(p.z)
+		};
+		sum.rem_euclid(2) == 0
+	}
+}
+
+impl Texture for CheckerTexture {
+	fn value(&self, u: f64, v: f64, p: Point3) -> Color {
+		if self.is_even(u, v, p) { self.even.value(u, v, p) } else { self.odd.value(u, v, p) }
+	}
+
+	fn filtered(&self, u: f64, v: f64, p: Point3, footprint: f64) -> Color {
+		if self.is_even(u, v, p) {
+			self.even.filtered(u, v, p, footprint)
+		} else {
+			self.odd.filtered(u, v, p, footprint)
+		}
+	}
+}
@@ -0,0 +1,199 @@
+use std::path::Path;
+
+use image::{DynamicImage, ImageError, Rgb32FImage};
+use serde::Deserialize;
+
+use crate::{
+	texture::Texture,
+	color::{Color, srgb_to_linear},
+	vec::Point3
+};
+
+/// Encoding of the values stored in an image file
+#[derive(Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ColorSpace {
+	/// Color maps, decoded to linear values
+	#[default]
+	Srgb,
+	/// Data maps such as roughness, used as stored
+	Linear,
+}
+
+/// What surface coordinates outside of [0, 1] look up
+#[derive(Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WrapMode {
+	/// The image tiles the surface
+	#[default]
+	Repeat,
+	/// The texels of the edges stretch outwards
+	Clamp,
+	/// The image tiles the surface, every other copy flipped
+	Mirror,
+}
+
+/// How texels are combined at a lookup
+#[derive(Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FilterMode {
+	/// Closest texel
+	Nearest,
+	/// Blend of the four closest texels
+	Bilinear,
+	/// Bilinear lookups of the two mip-map levels closest to the pixel footprint, blended
+	#[default]
+	Trilinear,
+}
+
+/// Texture mapping an image over the surface coordinates, u going right from the
+/// left edge and v going up from the bottom edge
+pub struct ImageTexture {
+	levels: Vec<Rgb32FImage>,	// Mip-map, each level half the size of the previous one
+	wrap: [WrapMode; 2],		// Along u and along v
+	filter: FilterMode,
+}
+
+impl ImageTexture {
+	/// Texture of an image of linear values, repeated and filtered trilinearly
+	pub fn new(image: Rgb32FImage) -> ImageTexture {
+		let mut levels: Vec<Rgb32FImage> = vec![image];
+		while let Some(level) = levels.last().and_then(downsample) {
+			levels.push(level);
+		}
+
+		ImageTexture { levels, wrap: [WrapMode::Repeat; 2], filter: FilterMode::Trilinear }
+	}
+
+	/// Texture of an image file of any format the image crate reads. Floating point
+	/// images are linear whatever the color space
+	pub fn load(path: &Path, color_space: ColorSpace) -> Result<ImageTexture, ImageError> {
+		let image: DynamicImage = image::open(path)?;
+		let linear: bool = color_space == ColorSpace::Linear
+			|| matches!(image, DynamicImage::ImageRgb32F(_) | DynamicImage::ImageRgba32F(_));
+
+		let mut image: Rgb32FImage = image.into_rgb32f();
+		if !linear {
+			for value in image.iter_mut() {
+				*value = srgb_to_linear(*value as f64) as f32;
+			}
+		}
+		Ok(ImageTexture::new(image))
+	}
+
+	pub fn with_wrap(mut self, wrap_u: WrapMode, wrap_v: WrapMode) -> ImageTexture {
+		self.wrap = [wrap_u, wrap_v];
+		self
+	}
+
+	pub fn with_filter(mut self, filter: FilterMode) -> ImageTexture {
+		self.filter = filter;
+		self
+	}
+
+	pub fn width(&self) -> u32 {
+		self.levels[0].width()
+	}
+
+	pub fn height(&self) -> u32 {
+		self.levels[0].height()
+	}
+
+	// Texel of a level at integer coordinates, wrapped into the image
+	fn texel(&self, level: &Rgb32FImage, i: i64, j: i64) -> Color {
+		let wrap = |index: i64, size: u32, mode: WrapMode| -> u32 {
+			let size: i64 = size as i64;
+			let index: i64 = match mode {
+				WrapMode::Repeat => index.rem_euclid(size),
+				WrapMode::Clamp => index.clamp(0, size - 1),
+				WrapMode::Mirror => {
+					let index: i64 = index.rem_euclid(2 * size);
+					if index < size { index } else { 2 * size - 1 - index }
+				}
+			};
+			index as u32
+		};
+
+		let pixel = level.get_pixel(wrap(i, level.width(), self.wrap[0]), wrap(j, level.height(), self.wrap[1]));
+		Color::new(pixel[0] as f64, pixel[1] as f64, pixel[2] as f64)
+	}
+
+	fn nearest(&self, level: &Rgb32FImage, u: f64, v: f64) -> Color {
+		// Image rows go down while v goes up
+		let x: f64 = u * level.width() as f64;
+		let y: f64 = (1.0 - v) * level.height() as f64;
+		self.texel(level, x.floor() as i64, y.floor() as i64)
+	}
+
+	fn bilinear(&self, level: &Rgb32FImage, u: f64, v: f64) -> Color {
+		// Texel centers sit half a texel inside their squares
+		let x: f64 = u * level.width() as f64 - 0.5;
+		let y: f64 = (1.0 - v) * level.height() as f64 - 0.5;
+		let (i, j) = (x.floor() as i64, y.floor() as i64);
+		let (fx, fy) = (x - x.floor(), y - y.floor());
+
+		(1.0 - fx) * (1.0 - fy) * self.texel(level, i, j)
+			+ fx * (1.0 - fy) * self.texel(level, i + 1, j)
+			+ (1.0 - fx) * fy * self.texel(level, i, j + 1)
+			+ fx * fy * self.texel(level, i + 1, j + 1)
+	}
+
+	// Level whose texels are about the size of the footprint, blended with the next one
+	fn trilinear(&self, u: f64, v: f64, footprint: f64) -> Color {
+		let size: f64 = self.width().max(self.height()) as f64;
+		let last: usize = self.levels.len() - 1;
+		let level: f64 = (footprint * size).max(1e-8).log2().clamp(0.0, last as f64);
+
+		let lower: usize = level.floor() as usize;
+		if lower == last {
+			return self.bilinear(&self.levels[last], u, v);
+		}
+		let blend: f64 = level - lower as f64;
+		(1.0 - blend) * self.bilinear(&self.levels[lower], u, v) + blend * self.bilinear(&self.levels[lower + 1], u, v)
+	}
+}
+
+// Next mip-map level, each texel the average of a 2x2 block. None past a single texel
+fn downsample(image: &Rgb32FImage) -> Option<Rgb32FImage> {
+	let (width, height) = image.dimensions();
+	if width.max(height) <= 1 || width.min(height) == 0 {
+		return None;
+	}
+
+	// Odd sizes leave their last row or column out of the blocks
+	let next_width: u32 = (width / 2).max(1);
+	let next_height: u32 = (height / 2).max(1);
+	Some(Rgb32FImage::from_fn(next_width, next_height, |x, y| {
+		let mut sum: [f32; 3] = [0.0; 3];
+		let mut count: f32 = 0.0;
+		for j in (2 * y)..(2 * y + 2).min(height) {
+			for i in (2 * x)..(2 * x + 2).min(width) {
+				let pixel = image.get_pixel(i, j);
+				for (total, value) in sum.iter_mut().zip(pixel.0) {
+					*total += value;
+				}
+				count += 1.0;
+			}
+		}
+		image::Rgb(sum.map(|total| total / count))
+	}))
+}
+
+impl Texture for ImageTexture {
+	fn value(&self, u: f64, v: f64, p: Point3) -> Color {
+		self.filtered(u, v, p, 0.0)
+	}
+
+	fn filtered(&self, u: f64, v: f64, _p: Point3, footprint: f64) -> Color {
+		let image: &Rgb32FImage = &self.levels[0];
+		if image.width() == 0 || image.height() == 0 {
+			return Color::new(0.0, 1.0, 1.0);
+		}
+
+		match self.filter {
+			FilterMode::Nearest => self.nearest(image, u, v),
+			FilterMode::Bilinear => self.bilinear(image, u, v),
+			FilterMode::Trilinear => self.trilinear(u, v, footprint)
+		}
+	}
+}
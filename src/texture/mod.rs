@@ -0,0 +1,26 @@
+mod solid_color;
+mod checker;
+mod image_texture;
+mod noise;
+
+use crate::{
+	color::Color,
+	vec::Point3
+};
+
+/// Color varying over surfaces, looked up at the surface coordinates (u, v) and at
+/// the hit point p
+pub trait Texture: Send + Sync {
+	fn value(&self, u: f64, v: f64, p: Point3) -> Color;
+
+	/// Average over the pixel footprint, `footprint` wide in surface coordinates. Only
+	/// textures with detail to filter away look past the center
+	fn filtered(&self, u: f64, v: f64, p: Point3, _footprint: f64) -> Color {
+		self.value(u, v, p)
+	}
+}
+
+pub use solid_color::SolidColor;
+pub use checker::CheckerTexture;
+pub use image_texture::{ImageTexture, ColorSpace, WrapMode, FilterMode};
+pub use noise::{Perlin, NoiseTexture};
@@ -0,0 +1,168 @@
+use crate::{
+	random::Rng,
+	texture::Texture,
+	color::{Color, BLACK, WHITE},
+	vec::{Point3, Vec3, dot}
+};
+
+const POINT_COUNT: usize = 256;
+
+/// Perlin gradient noise. The gradients and permutations come from their own
+/// generator, so the same seed always gives the same noise
+pub struct Perlin {
+	gradients: Vec<Vec3>,
+	perm_x: Vec<usize>,
+	perm_y: Vec<usize>,
+	perm_z: Vec<usize>,
+}
+
+impl Perlin {
+	pub fn new(seed: u64) -> Perlin {
+		let mut rng: Rng = Rng::new(seed);
+
+		// Random unit gradients, rejecting the short vectors that cannot be normalized
+		let mut gradients: Vec<Vec3> = Vec::with_capacity(POINT_COUNT);
+		while gradients.len() < POINT_COUNT {
+			let gradient: Vec3 = Vec3::new(
+				2.0 * rng.f64() - 1.0,
+				2.0 * rng.f64() - 1.0,
+				2.0 * rng.f64() - 1.0
+			);
+			let length_squared: f64 = gradient.length_squared();
+			if length_squared > 1e-6 && length_squared <= 1.0 {
+				gradients.push(gradient.unit_vector());
+			}
+		}
+
+		let mut permutation = || -> Vec<usize> {
+			let mut perm: Vec<usize> = (0..POINT_COUNT).collect();
+			rng.shuffle(&mut perm);
+			perm
+		};
+		let perm_x: Vec<usize> = permutation();
+		let perm_y: Vec<usize> = permutation();
+		let perm_z: Vec<usize> = permutation();
+
+		Perlin { gradients, perm_x, perm_y, perm_z }
+	}
+
+	/// Noise at `p`, in [-1, 1] and 0 at every integer lattice point
+	pub fn noise(&self, p: Point3) -> f64 {
+		let (i, j, k) = (p.x.floor() as i64, p.y.floor() as i64, p.z.floor() as i64);
+		let (u, v, w) = (p.x - p.x.floor(), p.y - p.y.floor(), p.z - p.z.floor());
+
+		// Hermite smoothing hides the lattice
+		let (uu, vv, ww) = (u * u * (3.0 - 2.0 * u), v * v * (3.0 - 2.0 * v), w * w * (3.0 - 2.0 * w));
+
+		let mut sum: f64 = 0.0;
+		for di in 0..2 {
+			for dj in 0..2 {
+				for dk in 0..2 {
+					let gradient: Vec3 = self.gradients[
+						self.perm_x[((i + di) & 255) as usize]
+						^ self.perm_y[((j + dj) & 255) as usize]
+						^ self.perm_z[((k + dk) & 255) as usize]
+					];
+					let (fi, fj, fk) = (di as f64, dj as f64, dk as f64);
+					let weight: Vec3 = Vec3::new(u - fi, v - fj, w - fk);
+					sum += (fi * uu + (1.0 - fi) * (1.0 - uu))
+						* (fj * vv + (1.0 - fj) * (1.0 - vv))
+						* (fk * ww + (1.0 - fk) * (1.0 - ww))
+						* dot(gradient, weight);
+				}
+			}
+		}
+		sum
+	}
+
+	/// Fractal sum of the absolute noise over `octaves`, each twice the frequency
+	/// and half the weight of the previous one
+	pub fn turbulence(&self, p: Point3, octaves: u32) -> f64 {
+		let mut sum: f64 = 0.0;
+		let mut point: Point3 = p;
+		let mut weight: f64 = 1.0;
+		for _ in 0..octaves {
+			sum += weight * self.noise(point).abs();
+			weight *= 0.5;
+			point = 2.0 * point;
+		}
+		sum
+	}
+}
+
+#[derive(Clone, Copy)]
+enum NoisePattern {
+	Noise,
+	Turbulence,
+	Marble,			// Veins of a sine wave along Z, bent by turbulence
+	Wood,			// Rings around the Y axis, bent by turbulence
+}
+
+/// Procedural texture blending two colors by a pattern of Perlin noise. The colors
+/// can be any value, so the same textures drive roughness or refraction indices
+pub struct NoiseTexture {
+	perlin: Perlin,
+	pattern: NoisePattern,
+	scale: f64,			// Multiplies the coordinates, marble veins repeat every 2π / scale
+	octaves: u32,
+	low: Color,
+	high: Color,
+}
+
+impl NoiseTexture {
+	/// Smooth noise
+	pub fn perlin(seed: u64, scale: f64) -> NoiseTexture {
+		NoiseTexture::with_pattern(NoisePattern::Noise, seed, scale)
+	}
+
+	/// Fractal noise with sharp creases
+	pub fn turbulence(seed: u64, scale: f64) -> NoiseTexture {
+		NoiseTexture::with_pattern(NoisePattern::Turbulence, seed, scale)
+	}
+
+	pub fn marble(seed: u64, scale: f64) -> NoiseTexture {
+		NoiseTexture::with_pattern(NoisePattern::Marble, seed, scale)
+	}
+
+	pub fn wood(seed: u64, scale: f64) -> NoiseTexture {
+		NoiseTexture::with_pattern(NoisePattern::Wood, seed, scale)
+	}
+
+	fn with_pattern(pattern: NoisePattern, seed: u64, scale: f64) -> NoiseTexture {
+		NoiseTexture { perlin: Perlin::new(seed), pattern, scale, octaves: 7, low: BLACK, high: WHITE }
+	}
+
+	/// Colors at the bottom and at the top of the pattern, black and white by default
+	pub fn with_colors(mut self, low: Color, high: Color) -> NoiseTexture {
+		self.low = low;
+		self.high = high;
+		self
+	}
+
+	/// Octaves of turbulence, 7 by default
+	pub fn with_octaves(mut self, octaves: u32) -> NoiseTexture {
+		self.octaves = octaves;
+		self
+	}
+
+	// Pattern value in [0, 1]
+	fn pattern(&self, p: Point3) -> f64 {
+		let p: Point3 = self.scale * p;
+		match self.pattern {
+			NoisePattern::Noise => 0.5 * (1.0 + self.perlin.noise(p)),
+			NoisePattern::Turbulence => self.perlin.turbulence(p, self.octaves).min(1.0),
+			NoisePattern::Marble => 0.5 * (1.0 + (p.z + 10.0 * self.perlin.turbulence(p, self.octaves)).sin()),
+			NoisePattern::Wood => {
+				let grain: f64 = (p.x * p.x + p.z * p.z).sqrt() + 0.5 * self.perlin.turbulence(p, self.octaves);
+				grain - grain.floor()
+			}
+		}
+	}
+}
+
+impl Texture for NoiseTexture {
+	fn value(&self, _u: f64, _v: f64, p: Point3) -> Color {
+		let t: f64 = self.pattern(p);
+		(1.0 - t) * self.low + t * self.high
+	}
+}
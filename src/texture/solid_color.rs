@@ -0,0 +1,21 @@
+use crate::{
+	texture::Texture,
+	color::Color,
+	vec::Point3
+};
+
+pub struct SolidColor {
+	albedo: Color,
+}
+
+impl SolidColor {
+	pub fn new(albedo: Color) -> SolidColor {
+		SolidColor { albedo }
+	}
+}
+
+impl Texture for SolidColor {
+	fn value(&self, _u: f64, _v: f64, _p: Point3) -> Color {
+		self.albedo
+	}
+}
@@ -0,0 +1,201 @@
+use std::sync::Arc;
+
+use crate::{
+	aabb::Aabb,
+	color::{Color, WHITE},
+	hit::{HitRecord, Hitable},
+	light::{Light, LightSample, same_distance},
+	material::Material,
+	onb::Onb,
+	sampler::Sampler,
+	ray::Ray,
+	vec::{Point3, Vec3, cross, dot}
+};
+
+/// Texture coordinates of a vertex
+pub type Uv = [f64; 2];
+
+/// Single triangle with optional per-vertex normals and texture coordinates
+pub struct Triangle {
+	positions: [Point3; 3],
+	normals: Option<[Vec3; 3]>,
+	uvs: Option<[Uv; 3]>,
+	material: Arc<dyn Material>,
+}
+
+impl Triangle {
+	pub fn new(p0: Point3, p1: Point3, p2: Point3, material: Arc<dyn Material>) -> Triangle {
+		Triangle {
+			positions: [p0, p1, p2],
+			normals: None,
+			uvs: None,
+			material
+		}
+	}
+
+	/// Shading normals interpolated over the triangle instead of the flat one
+	pub fn with_normals(mut self, normals: [Vec3; 3]) -> Triangle {
+		self.normals = Some(normals);
+		self
+	}
+
+	pub fn with_uvs(mut self, uvs: [Uv; 3]) -> Triangle {
+		self.uvs = Some(uvs);
+		self
+	}
+}
+
+impl Hitable for Triangle {
+	fn hit(&self, ray: &Ray, ray_tmin: f64, ray_tmax: f64, _sampler: &mut dyn Sampler) -> Option<HitRecord> {
+		let [p0, p1, p2] = self.positions;
+		let (t, b1, b2) = intersect_triangle(ray, p0, p1, p2, ray_tmin, ray_tmax)?;
+		Some(triangle_hit_record(ray, t, b1, b2, self.positions, self.normals, self.uvs, None, &self.material))
+	}
+
+	fn bounding_box(&self) -> Aabb {
+		triangle_bounding_box(self.positions)
+	}
+}
+
+impl Light for Triangle {
+	fn sample(&self, origin: Point3, sampler: &mut dyn Sampler) -> Option<LightSample> {
+		// Uniform point of the area, converted to a density in solid angle
+		let [p0, p1, p2] = self.positions;
+		let (r1, r2) = sampler.get_2d();
+		let sqrt_r1: f64 = r1.sqrt();
+		let point: Point3 = (1.0 - sqrt_r1) * p0 + (sqrt_r1 * (1.0 - r2)) * p1 + (sqrt_r1 * r2) * p2;
+
+		let to_point: Vec3 = point - origin;
+		let distance: f64 = to_point.length();
+		let direction: Vec3 = to_point / distance;
+
+		let n: Vec3 = cross(p1 - p0, p2 - p0);
+		let cosine: f64 = dot(direction, n.unit_vector()).abs();
+		if cosine < 1e-8 {
+			return None;
+		}
+
+		Some(LightSample {
+			direction,
+			distance,
+			pdf: distance * distance / (cosine * 0.5 * n.length())
+		})
+	}
+
+	fn pdf(&self, origin: Point3, direction: Vec3, distance: f64) -> f64 {
+		let [p0, p1, p2] = self.positions;
+		match intersect_triangle(&Ray::new(origin, direction), p0, p1, p2, 0.001, f64::INFINITY) {
+			Some((t, _, _)) if same_distance(t, distance) => {
+				let n: Vec3 = cross(p1 - p0, p2 - p0);
+				let cosine: f64 = dot(direction, n.unit_vector()).abs();
+				t * t / (cosine * 0.5 * n.length())
+			},
+			_ => 0.0
+		}
+	}
+}
+
+/// Möller–Trumbore intersection. Returns the ray parameter and the barycentric
+/// coordinates of p1 and p2 at the hit point
+pub fn intersect_triangle(ray: &Ray, p0: Point3, p1: Point3, p2: Point3, ray_tmin: f64, ray_tmax: f64) -> Option<(f64, f64, f64)> {
+	const EPSILON: f64 = 1e-12;
+
+	let edge1: Vec3 = p1 - p0;
+	let edge2: Vec3 = p2 - p0;
+	let pvec: Vec3 = cross(ray.dir(), edge2);
+	let det: f64 = dot(edge1, pvec);
+
+	// Ray parallel to the triangle plane
+	if det.abs() < EPSILON {
+		return None;
+	}
+	let inv_det: f64 = 1.0 / det;
+
+	let tvec: Vec3 = ray.origin() - p0;
+	let b1: f64 = dot(tvec, pvec) * inv_det;
+	if !(0.0..=1.0).contains(&b1) {
+		return None;
+	}
+
+	let qvec: Vec3 = cross(tvec, edge1);
+	let b2: f64 = dot(ray.dir(), qvec) * inv_det;
+	if b2 < 0.0 || b1 + b2 > 1.0 {
+		return None;
+	}
+
+	let t: f64 = dot(edge2, qvec) * inv_det;
+	if t < ray_tmin || t > ray_tmax {
+		return None;
+	}
+
+	Some((t, b1, b2))
+}
+
+/// Hit record of a triangle hit at the barycentric coordinates (b1, b2). Without
+/// per-vertex uvs the barycentric coordinates are used as surface coordinates
+#[allow(clippy::too_many_arguments)]
+pub fn triangle_hit_record(ray: &Ray, t: f64, b1: f64, b2: f64, positions: [Point3; 3], normals: Option<[Vec3; 3]>, uvs: Option<[Uv; 3]>, colors: Option<[Color; 3]>, material: &Arc<dyn Material>) -> HitRecord {
+	let b0: f64 = 1.0 - b1 - b2;
+	let mut geometric_normal: Vec3 = cross(positions[1] - positions[0], positions[2] - positions[0]).unit_vector();
+
+	// Vertex normals, when given, decide which side is the outside
+	let shading_normal: Vec3 = match normals {
+		Some([n0, n1, n2]) => {
+			let normal: Vec3 = b0 * n0 + b1 * n1 + b2 * n2;
+			if normal.length_squared() > 0.0 {
+				let normal: Vec3 = normal.unit_vector();
+				if dot(normal, geometric_normal) < 0.0 {
+					geometric_normal = -geometric_normal;
+				}
+				normal
+			} else {
+				geometric_normal
+			}
+		},
+		None => geometric_normal
+	};
+
+	let [uv0, uv1, uv2] = uvs.unwrap_or([[0.0, 0.0], [1.0, 0.0], [0.0, 1.0]]);
+	let u: f64 = b0 * uv0[0] + b1 * uv1[0] + b2 * uv2[0];
+	let v: f64 = b0 * uv0[1] + b1 * uv1[1] + b2 * uv2[1];
+
+	// Tangents solving the edges as combinations of the uv differences. Degenerate
+	// uvs get any pair of tangents perpendicular to the normal
+	let dp02: Vec3 = positions[0] - positions[2];
+	let dp12: Vec3 = positions[1] - positions[2];
+	let duv02: Uv = [uv0[0] - uv2[0], uv0[1] - uv2[1]];
+	let duv12: Uv = [uv1[0] - uv2[0], uv1[1] - uv2[1]];
+	let determinant: f64 = duv02[0] * duv12[1] - duv02[1] * duv12[0];
+	let (dpdu, dpdv) = if determinant.abs() > 1e-12 {
+		(
+			(duv12[1] * dp02 - duv02[1] * dp12) / determinant,
+			(duv02[0] * dp12 - duv12[0] * dp02) / determinant
+		)
+	} else {
+		let onb: Onb = Onb::new(geometric_normal);
+		(onb.u, onb.v)
+	};
+
+	let front_face: bool = dot(ray.dir(), geometric_normal) < 0.0;
+	HitRecord {
+		p: ray.at(t),
+		normal: if front_face { shading_normal } else { -shading_normal },
+		t,
+		u,
+		v,
+		dpdu,
+		dpdv,
+		uv_footprint: 0.0,
+		front_face,
+		color: colors.map_or(WHITE, |[c0, c1, c2]| b0 * c0 + b1 * c1 + b2 * c2),
+		material: material.clone()
+	}
+}
+
+pub fn triangle_bounding_box(positions: [Point3; 3]) -> Aabb {
+	// Padded so the box of an axis aligned triangle is never flat
+	const PADDING: f64 = 1e-4;
+	let bbox: Aabb = Aabb::new(positions[0], positions[1]).include_point(positions[2]);
+	let padding: Vec3 = Vec3::new(PADDING, PADDING, PADDING);
+	Aabb::new(bbox.min - padding, bbox.max + padding)
+}
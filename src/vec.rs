@@ -1,11 +1,14 @@
 use core::fmt;
 use core::fmt::Display;
 
-use std::ops::{Add, Sub, Mul, Div, Neg, AddAssign, DivAssign};
+use std::ops::{Add, Sub, Mul, Div, Neg, AddAssign, DivAssign, Index};
 
-use crate::random::{random_f64, random_f64_range};
+use serde::Deserialize;
 
-#[derive(Clone, Copy)]
+use crate::{random::Rng, sampler::Sampler};
+
+#[derive(Clone, Copy, Deserialize)]
+#[serde(from = "[f64; 3]")]
 pub struct Vec3 {
     pub x: f64,
     pub y: f64,
@@ -16,7 +19,7 @@ pub type Point3 = Vec3;
 
 impl Vec3 {
     pub const fn new (x: f64, y: f64, z: f64) -> Vec3 {
-        Vec3 {x: x, y: y, z: z}
+        Vec3 {x, y, z}
     }
 
     pub const fn zeros() -> Vec3 {
@@ -27,43 +30,60 @@ impl Vec3 {
         Vec3 { x: 1.0, y: 1.0, z: 1.0 }
     }
 
-    pub fn random() -> Vec3 {
-        Vec3 { x: random_f64(), y: random_f64(), z: random_f64() }
+    pub fn random(rng: &mut Rng) -> Vec3 {
+        Vec3 { x: rng.f64(), y: rng.f64(), z: rng.f64() }
     }
 
-    pub fn random_range(min: f64, max: f64) -> Vec3 {
+    pub fn random_range(rng: &mut Rng, min: f64, max: f64) -> Vec3 {
         Vec3 { 
-            x: random_f64_range(min, max),
-            y: random_f64_range(min, max),
-            z: random_f64_range(min, max)
+            x: rng.f64_range(min, max),
+            y: rng.f64_range(min, max),
+            z: rng.f64_range(min, max)
         }
     }
 
-    pub fn random_in_unit_sphere() -> Vec3 {
-        let mut p = Vec3::zeros();
-        while p.length() < 1.0 {
-            p = Vec3::random_range(-1.0, 1.0);
-        }
-        p
+    // The samplers below warp the dimensions directly instead of rejecting points,
+    // so stratified samples stay stratified on the sphere and the disk
+    pub fn random_in_unit_sphere(sampler: &mut dyn Sampler) -> Vec3 {
+        let direction: Vec3 = Vec3::random_unit_vector(sampler);
+        sampler.get_1d().cbrt() * direction
     }
 
-    pub fn random_in_unit_disk() -> Vec3 {
-        loop {
-            let p: Vec3 = Vec3::new(random_f64_range(-1.0, 1.0),
-            random_f64_range(-1.0, 1.0), 0.0);
-
-            if p.length_squared() < 1.0 {
-                return p;
-            }
+    // Concentric mapping of the square on the disk, from Shirley and Chiu
+    pub fn random_in_unit_disk(sampler: &mut dyn Sampler) -> Vec3 {
+        let (u, v) = sampler.get_2d();
+        let (a, b) = (2.0 * u - 1.0, 2.0 * v - 1.0);
+        if a == 0.0 && b == 0.0 {
+            return Vec3::zeros();
         }
+
+        let (r, phi) = if a.abs() > b.abs() {
+            (a, std::f64::consts::FRAC_PI_4 * (b / a))
+        } else {
+            (b, std::f64::consts::FRAC_PI_2 - std::f64::consts::FRAC_PI_4 * (a / b))
+        };
+        Vec3::new(r * phi.cos(), r * phi.sin(), 0.0)
+    }
+
+    pub fn random_unit_vector(sampler: &mut dyn Sampler) -> Vec3 {
+        let (u, v) = sampler.get_2d();
+        let z: f64 = 1.0 - 2.0 * u;
+        let r: f64 = (1.0 - z * z).max(0.0).sqrt();
+        let phi: f64 = 2.0 * std::f64::consts::PI * v;
+        Vec3::new(r * phi.cos(), r * phi.sin(), z)
     }
 
-    pub fn random_unit_vector() -> Vec3 {
-        Vec3::random_in_unit_sphere()
+    // Direction around the z axis, with density cos(theta) / pi
+    pub fn random_cosine_direction(sampler: &mut dyn Sampler) -> Vec3 {
+        let (r1, r2) = sampler.get_2d();
+
+        let phi: f64 = 2.0 * std::f64::consts::PI * r1;
+        let sqrt_r2: f64 = r2.sqrt();
+        Vec3::new(phi.cos() * sqrt_r2, phi.sin() * sqrt_r2, (1.0 - r2).sqrt())
     }
 
-    pub fn random_on_hemisphere(normal: &Vec3) -> Vec3 {
-        let on_unit_sphere = Vec3::random_unit_vector();
+    pub fn random_on_hemisphere(sampler: &mut dyn Sampler, normal: &Vec3) -> Vec3 {
+        let on_unit_sphere = Vec3::random_unit_vector(sampler);
 
         if dot(on_unit_sphere, *normal) > 0.0 {
             on_unit_sphere
@@ -111,7 +131,7 @@ impl Vec3 {
 
     pub fn near_zero(self) -> bool {
         const THRESHOLD: f64 = 1.0e-8;
-        (self.x < THRESHOLD) && (self.y < THRESHOLD) && (self.z < THRESHOLD)
+        (self.x.abs() < THRESHOLD) && (self.y.abs() < THRESHOLD) && (self.z.abs() < THRESHOLD)
     }
 }
 
@@ -211,6 +231,25 @@ impl Mul<Vec3> for f64 {
     }
 }
 
+impl From<[f64; 3]> for Vec3 {
+    fn from(v: [f64; 3]) -> Self {
+        Vec3::new(v[0], v[1], v[2])
+    }
+}
+
+impl Index<usize> for Vec3 {
+    type Output = f64;
+
+    fn index(&self, axis: usize) -> &Self::Output {
+        match axis {
+            0 => &self.x,
+            1 => &self.y,
+            2 => &self.z,
+            _ => panic!("Vec3 axis index out of range: {}", axis)
+        }
+    }
+}
+
 impl Display for Vec3 {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "({}, {}, {})", self.x, self.y, self.z)
@@ -238,4 +277,148 @@ pub fn refract(uv: Vec3, n: Vec3, etai_over_etat: f64) -> Vec3 {
     let r_out_perp: Vec3 = etai_over_etat * (uv + cos_theta * n);
     let r_out_parallel: Vec3 = -(1.0 - r_out_perp.length_squared()).abs().sqrt() * n;
     r_out_perp + r_out_parallel
-}
\ No newline at end of file
+}
+
+/// Affine transform as a row-major 4x4 matrix, the last row is always (0, 0, 0, 1).
+/// Products apply the right-hand transform first
+#[derive(Clone, Copy, PartialEq)]
+pub struct Mat4 {
+    pub m: [[f64; 4]; 4]
+}
+
+impl Mat4 {
+    pub const fn identity() -> Mat4 {
+        Mat4 {
+            m: [
+                [1.0, 0.0, 0.0, 0.0],
+                [0.0, 1.0, 0.0, 0.0],
+                [0.0, 0.0, 1.0, 0.0],
+                [0.0, 0.0, 0.0, 1.0]
+            ]
+        }
+    }
+
+    /// Matrix from its columns, the layout of glTF and most file formats
+    pub fn from_columns(columns: [[f64; 4]; 4]) -> Mat4 {
+        let mut m: [[f64; 4]; 4] = [[0.0; 4]; 4];
+        for (column, values) in columns.iter().enumerate() {
+            for (row, value) in values.iter().enumerate() {
+                m[row][column] = *value;
+            }
+        }
+        Mat4 { m }
+    }
+
+    pub fn translation(offset: Vec3) -> Mat4 {
+        let mut matrix: Mat4 = Mat4::identity();
+        matrix.m[0][3] = offset.x;
+        matrix.m[1][3] = offset.y;
+        matrix.m[2][3] = offset.z;
+        matrix
+    }
+
+    pub fn scale(factors: Vec3) -> Mat4 {
+        let mut matrix: Mat4 = Mat4::identity();
+        matrix.m[0][0] = factors.x;
+        matrix.m[1][1] = factors.y;
+        matrix.m[2][2] = factors.z;
+        matrix
+    }
+
+    /// Counterclockwise rotation around the axis, looking down the axis towards the origin
+    pub fn rotation(axis: Vec3, degrees: f64) -> Mat4 {
+        let a: Vec3 = axis.unit_vector();
+        let (sin, cos) = degrees.to_radians().sin_cos();
+        let k: f64 = 1.0 - cos;
+
+        Mat4 {
+            m: [
+                [cos + a.x * a.x * k,       a.x * a.y * k - a.z * sin, a.x * a.z * k + a.y * sin, 0.0],
+                [a.y * a.x * k + a.z * sin, cos + a.y * a.y * k,       a.y * a.z * k - a.x * sin, 0.0],
+                [a.z * a.x * k - a.y * sin, a.z * a.y * k + a.x * sin, cos + a.z * a.z * k,       0.0],
+                [0.0,                       0.0,                       0.0,                       1.0]
+            ]
+        }
+    }
+
+    pub fn transform_point(&self, p: Point3) -> Point3 {
+        self.transform_vector(p) + Vec3::new(self.m[0][3], self.m[1][3], self.m[2][3])
+    }
+
+    pub fn transform_vector(&self, v: Vec3) -> Vec3 {
+        let m = &self.m;
+        Vec3::new(
+            m[0][0] * v.x + m[0][1] * v.y + m[0][2] * v.z,
+            m[1][0] * v.x + m[1][1] * v.y + m[1][2] * v.z,
+            m[2][0] * v.x + m[2][1] * v.y + m[2][2] * v.z
+        )
+    }
+
+    pub fn transpose(&self) -> Mat4 {
+        let mut m: [[f64; 4]; 4] = [[0.0; 4]; 4];
+        for (row, values) in self.m.iter().enumerate() {
+            for (column, value) in values.iter().enumerate() {
+                m[column][row] = *value;
+            }
+        }
+        Mat4 { m }
+    }
+
+    pub fn determinant(&self) -> f64 {
+        let [x, y, z] = self.columns();
+        dot(x, cross(y, z))
+    }
+
+    /// Inverse transform, None when the matrix collapses space on a plane or less
+    pub fn inverse(&self) -> Option<Mat4> {
+        let determinant: f64 = self.determinant();
+        if determinant == 0.0 || !determinant.is_finite() {
+            return None;
+        }
+
+        // Rows of the inverse of the linear part are the cross products of its columns
+        let [x, y, z] = self.columns();
+        let rows: [Vec3; 3] = [cross(y, z) / determinant, cross(z, x) / determinant, cross(x, y) / determinant];
+
+        let mut inverse: Mat4 = Mat4::identity();
+        for (row, values) in rows.iter().enumerate() {
+            inverse.m[row][0] = values.x;
+            inverse.m[row][1] = values.y;
+            inverse.m[row][2] = values.z;
+        }
+        let translation: Vec3 = -inverse.transform_vector(Vec3::new(self.m[0][3], self.m[1][3], self.m[2][3]));
+        inverse.m[0][3] = translation.x;
+        inverse.m[1][3] = translation.y;
+        inverse.m[2][3] = translation.z;
+        Some(inverse)
+    }
+
+    fn columns(&self) -> [Vec3; 3] {
+        let m = &self.m;
+        [
+            Vec3::new(m[0][0], m[1][0], m[2][0]),
+            Vec3::new(m[0][1], m[1][1], m[2][1]),
+            Vec3::new(m[0][2], m[1][2], m[2][2])
+        ]
+    }
+}
+
+impl Default for Mat4 {
+    fn default() -> Self {
+        Mat4::identity()
+    }
+}
+
+impl Mul for Mat4 {
+    type Output = Mat4;
+
+    fn mul(self, rhs: Self) -> Self::Output {
+        let mut m: [[f64; 4]; 4] = [[0.0; 4]; 4];
+        for (row, values) in m.iter_mut().enumerate() {
+            for (column, value) in values.iter_mut().enumerate() {
+                *value = (0..4).map(|k| self.m[row][k] * rhs.m[k][column]).sum();
+            }
+        }
+        Mat4 { m }
+    }
+}
@@ -0,0 +1,264 @@
+use std::{
+	fmt,
+	fs,
+	path::{Path, PathBuf}
+};
+
+use serde::Deserialize;
+
+use crate::vec::Point3;
+
+#[derive(Debug)]
+pub enum VoxelGridError {
+	Io(PathBuf, std::io::Error),
+	Parse { path: PathBuf, message: String },
+}
+
+impl fmt::Display for VoxelGridError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			VoxelGridError::Io(path, err) => write!(f, "{}: {}", path.display(), err),
+			VoxelGridError::Parse { path, message } => write!(f, "{}: {}", path.display(), message)
+		}
+	}
+}
+
+impl std::error::Error for VoxelGridError {}
+
+/// Type of the values stored in a voxel file. Unsigned integers are mapped to [0, 1]
+/// by their largest value, signed ones to [-1, 1], floats are kept as they are
+#[derive(Clone, Copy, PartialEq, Debug, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum VoxelType {
+	Int8,
+	#[default]
+	Uint8,
+	Int16,
+	Uint16,
+	Int32,
+	Uint32,
+	Float32,
+	Float64,
+}
+
+impl VoxelType {
+	fn size(self) -> usize {
+		match self {
+			VoxelType::Int8 | VoxelType::Uint8 => 1,
+			VoxelType::Int16 | VoxelType::Uint16 => 2,
+			VoxelType::Int32 | VoxelType::Uint32 | VoxelType::Float32 => 4,
+			VoxelType::Float64 => 8
+		}
+	}
+
+	// Names of the NRRD type field, with their synonyms
+	fn from_nrrd(name: &str) -> Option<VoxelType> {
+		match name {
+			"signed char" | "int8" | "int8_t" => Some(VoxelType::Int8),
+			"uchar" | "unsigned char" | "uint8" | "uint8_t" => Some(VoxelType::Uint8),
+			"short" | "short int" | "signed short" | "signed short int" | "int16" | "int16_t" => Some(VoxelType::Int16),
+			"ushort" | "unsigned short" | "unsigned short int" | "uint16" | "uint16_t" => Some(VoxelType::Uint16),
+			"int" | "signed int" | "int32" | "int32_t" => Some(VoxelType::Int32),
+			"uint" | "unsigned int" | "uint32" | "uint32_t" => Some(VoxelType::Uint32),
+			"float" => Some(VoxelType::Float32),
+			"double" => Some(VoxelType::Float64),
+			_ => None
+		}
+	}
+
+	fn read(self, bytes: &[u8], big_endian: bool) -> f32 {
+		macro_rules! decode {
+			($type:ty) => {{
+				let array = bytes.try_into().unwrap();
+				if big_endian { <$type>::from_be_bytes(array) } else { <$type>::from_le_bytes(array) }
+			}};
+		}
+		match self {
+			VoxelType::Int8 => bytes[0] as i8 as f32 / i8::MAX as f32,
+			VoxelType::Uint8 => bytes[0] as f32 / u8::MAX as f32,
+			VoxelType::Int16 => decode!(i16) as f32 / i16::MAX as f32,
+			VoxelType::Uint16 => decode!(u16) as f32 / u16::MAX as f32,
+			VoxelType::Int32 => (decode!(i32) as f64 / i32::MAX as f64) as f32,
+			VoxelType::Uint32 => (decode!(u32) as f64 / u32::MAX as f64) as f32,
+			VoxelType::Float32 => decode!(f32),
+			VoxelType::Float64 => decode!(f64) as f32
+		}
+	}
+}
+
+/// Dense grid of values filling the unit cube, X varying fastest then Y then Z.
+/// Values sit at the centers of the voxels and are blended between them
+pub struct VoxelGrid {
+	size: [usize; 3],
+	values: Vec<f32>,
+	max: f64,
+}
+
+impl VoxelGrid {
+	/// Fails if the grid has no voxels, or not one value per voxel
+	pub fn new(size: [usize; 3], values: Vec<f32>) -> Result<VoxelGrid, String> {
+		let count: usize = voxel_count(size)?;
+		if values.len() != count {
+			return Err(format!("{} values for {} voxels", values.len(), count));
+		}
+		let max: f64 = values.iter().fold(0.0f32, |max, value| max.max(*value)) as f64;
+		Ok(VoxelGrid { size, values, max })
+	}
+
+	pub fn size(&self) -> [usize; 3] {
+		self.size
+	}
+
+	/// Largest value of the grid, or 0 if they are all negative
+	pub fn max(&self) -> f64 {
+		self.max
+	}
+
+	fn value(&self, x: usize, y: usize, z: usize) -> f64 {
+		self.values[(z * self.size[1] + y) * self.size[0] + x] as f64
+	}
+
+	/// Trilinear interpolation of the 8 voxels around a point of the unit cube.
+	/// Points outside take the value of the closest voxels
+	pub fn lookup(&self, p: Point3) -> f64 {
+		let mut corners: [(usize, usize, f64); 3] = [(0, 0, 0.0); 3];
+		for (axis, corner) in corners.iter_mut().enumerate() {
+			let last: usize = self.size[axis] - 1;
+			let coordinate: f64 = (p[axis] * self.size[axis] as f64 - 0.5).clamp(0.0, last as f64);
+			let low: usize = (coordinate.floor() as usize).min(last);
+			*corner = (low, (low + 1).min(last), coordinate - low as f64);
+		}
+		let [(x0, x1, fx), (y0, y1, fy), (z0, z1, fz)] = corners;
+
+		let lerp = |a: f64, b: f64, t: f64| a + t * (b - a);
+		let plane = |z: usize| -> f64 {
+			lerp(
+				lerp(self.value(x0, y0, z), self.value(x1, y0, z), fx),
+				lerp(self.value(x0, y1, z), self.value(x1, y1, z), fx),
+				fy
+			)
+		};
+		lerp(plane(z0), plane(z1), fz)
+	}
+}
+
+/// Grid of a headerless file of `size` little-endian values of the given type
+pub fn load_raw(path: &Path, size: [usize; 3], voxel_type: VoxelType) -> Result<VoxelGrid, VoxelGridError> {
+	let bytes: Vec<u8> = fs::read(path).map_err(|err| VoxelGridError::Io(path.to_path_buf(), err))?;
+	decode(&bytes, size, voxel_type, false)
+		.map_err(|message| VoxelGridError::Parse { path: path.to_path_buf(), message })
+}
+
+/// Grid of a three dimensional NRRD file with raw encoding, the data following the
+/// header or in a detached data file. Other fields, like the spacings, are ignored
+pub fn load_nrrd(path: &Path) -> Result<VoxelGrid, VoxelGridError> {
+	let bytes: Vec<u8> = fs::read(path).map_err(|err| VoxelGridError::Io(path.to_path_buf(), err))?;
+	let parse_error = |message: String| VoxelGridError::Parse { path: path.to_path_buf(), message };
+	if !bytes.starts_with(b"NRRD") {
+		return Err(parse_error("not an NRRD file".to_string()));
+	}
+
+	// The header is text up to the first empty line
+	let mut voxel_type: Option<VoxelType> = None;
+	let mut size: Option<[usize; 3]> = None;
+	let mut big_endian: bool = false;
+	let mut data_file: Option<PathBuf> = None;
+	let mut offset: usize = 0;
+	let mut header_end: Option<usize> = None;
+	for line in bytes.split(|byte| *byte == b'\n') {
+		let line_start: usize = offset;
+		offset += line.len() + 1;
+		let line: &str = std::str::from_utf8(line).map_err(|_| parse_error("header is not valid text".to_string()))?.trim_end_matches('\r');
+		if line.is_empty() {
+			header_end = Some(offset.min(bytes.len()));
+			break;
+		}
+		// Magic line, comments and key/value pairs
+		if line_start == 0 || line.starts_with('#') || line.contains(":=") {
+			continue;
+		}
+
+		let (field, value) = line.split_once(':').ok_or_else(|| parse_error(format!("malformed header line '{}'", line)))?;
+		let value: &str = value.trim();
+		match field.trim() {
+			"type" => voxel_type = Some(VoxelType::from_nrrd(value)
+				.ok_or_else(|| parse_error(format!("unsupported type '{}'", value)))?),
+			"dimension" if value != "3" => return Err(parse_error(format!("dimension {} instead of 3", value))),
+			"sizes" => {
+				let sizes: Vec<usize> = value.split_whitespace()
+					.map(|size| size.parse::<usize>())
+					.collect::<Result<_, _>>()
+					.map_err(|_| parse_error(format!("invalid sizes '{}'", value)))?;
+				size = Some(sizes.try_into().map_err(|_| parse_error(format!("sizes '{}' are not three", value)))?);
+			},
+			"encoding" if value != "raw" => return Err(parse_error(format!("unsupported encoding '{}', only raw is", value))),
+			"endian" => big_endian = value == "big",
+			"data file" | "datafile" => data_file = Some(path.parent().unwrap_or(Path::new("")).join(value)),
+			_ => ()
+		}
+	}
+
+	let voxel_type: VoxelType = voxel_type.ok_or_else(|| parse_error("missing type".to_string()))?;
+	let size: [usize; 3] = size.ok_or_else(|| parse_error("missing sizes".to_string()))?;
+	match data_file {
+		Some(data_file) => {
+			let data: Vec<u8> = fs::read(&data_file).map_err(|err| VoxelGridError::Io(data_file.clone(), err))?;
+			decode(&data, size, voxel_type, big_endian)
+				.map_err(|message| VoxelGridError::Parse { path: data_file, message })
+		},
+		None => {
+			let header_end: usize = header_end.ok_or_else(|| parse_error("header has no end".to_string()))?;
+			decode(&bytes[header_end..], size, voxel_type, big_endian).map_err(parse_error)
+		}
+	}
+}
+
+// Count of voxels of a grid, an error when there are none or more than can be addressed
+fn voxel_count(size: [usize; 3]) -> Result<usize, String> {
+	let count: usize = size[0].checked_mul(size[1])
+		.and_then(|count| count.checked_mul(size[2]))
+		.ok_or_else(|| format!("grid of {}x{}x{} voxels is too large", size[0], size[1], size[2]))?;
+	if count == 0 {
+		return Err("grid has no voxels".to_string());
+	}
+	Ok(count)
+}
+
+fn decode(bytes: &[u8], size: [usize; 3], voxel_type: VoxelType, big_endian: bool) -> Result<VoxelGrid, String> {
+	let count: usize = voxel_count(size)?;
+	let expected: usize = count.checked_mul(voxel_type.size())
+		.ok_or_else(|| format!("grid of {}x{}x{} voxels is too large", size[0], size[1], size[2]))?;
+	if bytes.len() < expected {
+		return Err(format!("{} bytes of data instead of {}", bytes.len(), expected));
+	}
+
+	let values: Vec<f32> = bytes[..expected]
+		.chunks_exact(voxel_type.size())
+		.map(|value| voxel_type.read(value, big_endian))
+		.collect();
+	VoxelGrid::new(size, values)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn decode_rejects_sizes_that_overflow() {
+		let bytes: [u8; 16] = [0; 16];
+		for size in [[usize::MAX, 2, 1], [1 << 40, 1 << 40, 1]] {
+			let error: String = decode(&bytes, size, VoxelType::Uint8, false).err().expect("overflowing size");
+			assert!(error.contains("too large"), "{}", error);
+		}
+		let error: String = decode(&bytes, [usize::MAX / 4, 1, 1], VoxelType::Float64, false).err().expect("overflowing byte count");
+		assert!(error.contains("too large"), "{}", error);
+		assert!(decode(&bytes, [2, 2, 2], VoxelType::Uint16, false).is_ok());
+	}
+
+	#[test]
+	fn new_rejects_mismatched_values() {
+		assert!(VoxelGrid::new([2, 2, 2], vec![0.0; 7]).is_err());
+		assert!(VoxelGrid::new([0, 2, 2], Vec::new()).is_err());
+		assert_eq!(VoxelGrid::new([2, 2, 2], vec![0.5; 8]).map(|grid| grid.max()), Ok(0.5));
+	}
+}